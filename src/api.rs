@@ -3,19 +3,27 @@
 use exonum::api::{self, ServiceApiBuilder, ServiceApiState};
 use exonum::crypto::{Hash, PublicKey};
 
-use exonum::blockchain::{Transaction};
+use exonum::blockchain::{self, BlockProof, Transaction};
 use exonum::explorer::{BlockchainExplorer, TransactionInfo};
 use exonum::node::{TransactionSend};
 use exonum::helpers::Height;
+use exonum::storage::{ListProof, MapProof};
 
 use std::{thread, time};
 
-use auction::{AuctionState, Bid};
-use product::ProductState;
-use user::User;
+use auction::{Ask, AuctionKind, AuctionState, Bid, BidCommitment, DeadlineExtension};
+use category::{category_id, Category};
+use listing::Listing;
+use offer::Offer;
+use order::{class_id, Order, OrderSide, Trade};
+use product::{OwnershipRecord, ProductState};
+use user::{TransferRecord, User};
+
+use share::ShareListing;
 
 use schema;
 use transactions::Transactions;
+use AUCTION_SERVICE_ID;
 
 //use static_channel;
 
@@ -27,6 +35,11 @@ pub struct ProductQuery {
     pub id: Hash,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BarcodeQuery {
+    pub barcode: String,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct UserQuery {
     pub pub_key: PublicKey,
@@ -37,6 +50,28 @@ pub struct AuctionQuery {
     pub id: u64,
 }
 
+/// Product search filters, omitted filters match any product.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductSearchQuery {
+    /// Text contained in the name or the description, case insensitive.
+    pub text: Option<String>,
+    pub category: Option<String>,
+    pub condition: Option<u8>,
+    /// Attribute key, `value` additionally filters by its value.
+    pub key: Option<String>,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryQuery {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassQuery {
+    pub class: String,
+}
+
 /// Response to an incoming transaction returned by the REST API.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TransactionResponse {
@@ -46,6 +81,74 @@ pub struct TransactionResponse {
     pub position_in_block: u64,
 }
 
+/// Auction information returned by the REST API.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuctionInfo {
+    /// Auction state.
+    pub auction_state: AuctionState,
+    /// Bids made in the auction.
+    pub bids: Vec<Bid>,
+    /// History of deadline extensions.
+    pub extensions: Vec<DeadlineExtension>,
+    /// Sealed bid commitments.
+    pub commitments: Vec<BidCommitment>,
+    /// Asks made in the reverse auction.
+    pub asks: Vec<Ask>,
+    /// Current price of the Dutch auction.
+    pub current_price: Option<u64>,
+}
+
+/// Shares held by the user.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShareHolding {
+    /// Shareholder.
+    pub public_key: PublicKey,
+    /// Number of shares.
+    pub amount: u64,
+}
+
+/// Shares of the split product returned by the REST API.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductSharesInfo {
+    /// Shareholders.
+    pub holders: Vec<ShareHolding>,
+    /// Shares offered for sale.
+    pub listings: Vec<ShareListing>,
+}
+
+/// Ownership history of the product.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductHistory {
+    /// Proof of the history records.
+    pub proof: ListProof<OwnershipRecord>,
+    /// Ownership changes in chronological order.
+    pub records: Vec<OwnershipRecord>,
+}
+
+/// Product provenance returned by the REST API. Proofs lead from the state hash of the latest
+/// block to the product history.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductHistoryInfo {
+    /// Proof of the latest block.
+    pub block_proof: BlockProof,
+    /// Proof of the `products_states` table in the state hash.
+    pub to_table: MapProof<Hash, Hash>,
+    /// Proof of the product state in the table.
+    pub to_product: MapProof<Hash, ProductState>,
+    /// Product history, if the product exists.
+    pub history: Option<ProductHistory>,
+}
+
+/// Order book of the product class returned by the REST API.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrderBookInfo {
+    /// Resting buy orders, the highest price first.
+    pub bids: Vec<Order>,
+    /// Resting sell orders, the lowest price first.
+    pub asks: Vec<Order>,
+    /// Trades of the class.
+    pub trades: Vec<Trade>,
+}
 
 impl PublicApi {
     /// User profile.
@@ -64,6 +167,24 @@ impl PublicApi {
         Ok(users)
     }
 
+    /// Funds transfers sent or received by user.
+    fn get_user_transfers(
+        state: &ServiceApiState,
+        query: UserQuery,
+        ) -> api::Result<Option<Vec<TransferRecord>>> {
+        let snapshot = state.snapshot();
+        let schema = schema::AuctionSchema::new(snapshot);
+
+        Ok(schema.users().get(&query.pub_key).map(|user| {
+            let user_transfers = schema.user_transfers(user.pub_key());
+            let transfers = user_transfers
+                .into_iter()
+                .map(|transfer_id| schema.transfers().get(transfer_id).unwrap())
+                .collect();
+            transfers
+        }))
+    }
+
     /// Product profile.
     fn get_product(
         state: &ServiceApiState,
@@ -74,6 +195,65 @@ impl PublicApi {
         Ok(schema.products_states().get(&query.id))
     }
 
+    /// Product profile by its barcode.
+    fn get_product_by_barcode(
+        state: &ServiceApiState,
+        query: BarcodeQuery,
+        ) -> api::Result<Option<ProductState>> {
+        let snapshot = state.snapshot();
+        let schema = schema::AuctionSchema::new(snapshot);
+        Ok(schema
+           .barcodes()
+           .get(&query.barcode)
+           .and_then(|product_id| schema.products_states().get(&product_id)))
+    }
+
+    /// Shareholders of the split product and shares offered for sale.
+    fn get_product_shares(
+        state: &ServiceApiState,
+        query: ProductQuery,
+        ) -> api::Result<ProductSharesInfo> {
+        let snapshot = state.snapshot();
+        let schema = schema::AuctionSchema::new(snapshot);
+        let holders = schema
+            .product_shares(&query.id)
+            .iter()
+            .map(|(public_key, amount)| ShareHolding { public_key, amount })
+            .collect();
+        let listings = schema.share_listings(&query.id).values().collect();
+        Ok(ProductSharesInfo { holders, listings })
+    }
+
+    /// Product ownership history with proofs.
+    fn get_product_history(
+        state: &ServiceApiState,
+        query: ProductQuery,
+        ) -> api::Result<ProductHistoryInfo> {
+        let snapshot = state.snapshot();
+        let general_schema = blockchain::Schema::new(&snapshot);
+        let schema = schema::AuctionSchema::new(&snapshot);
+
+        let max_height = general_schema.block_hashes_by_height().len() - 1;
+        let block_proof = general_schema
+            .block_and_precommits(Height(max_height))
+            .unwrap();
+
+        // `products_states` is the second table of the service state hash.
+        let to_table: MapProof<Hash, Hash> =
+            general_schema.get_proof_to_service_table(AUCTION_SERVICE_ID, 1);
+        let to_product: MapProof<Hash, ProductState> =
+            schema.products_states().get_proof(query.id);
+
+        let history = schema.products_states().get(&query.id).map(|_| {
+            let history = schema.product_history(&query.id);
+            let proof = history.get_range_proof(0, history.len());
+            let records = history.iter().collect();
+            ProductHistory { proof, records }
+        });
+
+        Ok(ProductHistoryInfo { block_proof, to_table, to_product, history })
+    }
+
     /// All products.
     fn get_products(state: &ServiceApiState, _query: ()) -> api::Result<Vec<ProductState>> {
         let snapshot = state.snapshot();
@@ -83,6 +263,40 @@ impl PublicApi {
         Ok(products)
     }
 
+    /// Products matching the search filters.
+    fn search_products(
+        state: &ServiceApiState,
+        query: ProductSearchQuery,
+        ) -> api::Result<Vec<ProductState>> {
+        let snapshot = state.snapshot();
+        let schema = schema::AuctionSchema::new(snapshot);
+        let text = query.text.as_ref().map(|text| text.to_lowercase());
+
+        let products = schema
+            .products_states()
+            .values()
+            .filter(|state| {
+                let product = state.product();
+                let text_found = text.as_ref().map_or(true, |text| {
+                    product.name().to_lowercase().contains(text.as_str())
+                        || product.description().to_lowercase().contains(text.as_str())
+                });
+                let attribute_found = query.key.as_ref().map_or(true, |key| {
+                    match (product.attribute(key), query.value.as_ref()) {
+                        (Some(value), Some(expected)) => value == *expected,
+                        (found, None) => found.is_some(),
+                        (None, Some(_)) => false,
+                    }
+                });
+                text_found
+                    && attribute_found
+                    && query.category.as_ref().map_or(true, |category| product.category() == category)
+                    && query.condition.map_or(true, |condition| product.condition() == condition)
+            })
+            .collect();
+        Ok(products)
+    }
+
     /// User products list.
     fn get_user_products(
         state: &ServiceApiState,
@@ -119,13 +333,34 @@ impl PublicApi {
         }))
     }
 
-    /// Auctions and bids by auction identifier.
+    /// Auction, its bids and deadline extensions by auction identifier.
     fn get_auction_with_bids(
         state: &ServiceApiState,
         query: AuctionQuery,
-        ) -> api::Result<Option<(AuctionState, Vec<Bid>)>> {
+        ) -> api::Result<Option<(AuctionState, Vec<Bid>, Vec<DeadlineExtension>)>> {
+        let snapshot = state.snapshot();
+        let schema = schema::AuctionSchema::new(snapshot);
+
+        Ok(schema
+           .auctions()
+           .get(query.id)
+           .map(|auction_state| {
+               let auction_bids = schema.auction_bids(auction_state.id());
+               let bids = auction_bids.into_iter().collect();
+               let auction_extensions = schema.auction_extensions(auction_state.id());
+               let extensions = auction_extensions.into_iter().collect();
+               (auction_state, bids, extensions)
+           }))
+    }
+
+    /// Auction, bids, deadline extensions, commitments and asks by auction identifier.
+    fn get_auction_info(
+        state: &ServiceApiState,
+        query: AuctionQuery,
+        ) -> api::Result<Option<AuctionInfo>> {
         let snapshot = state.snapshot();
         let schema = schema::AuctionSchema::new(snapshot);
+        let height = schema.height();
 
         Ok(schema
            .auctions()
@@ -133,7 +368,17 @@ impl PublicApi {
            .map(|auction_state| {
                let auction_bids = schema.auction_bids(auction_state.id());
                let bids = auction_bids.into_iter().collect();
-               (auction_state, bids)
+               let auction_extensions = schema.auction_extensions(auction_state.id());
+               let extensions = auction_extensions.into_iter().collect();
+               let auction_commitments = schema.auction_commitments(auction_state.id());
+               let commitments = auction_commitments.values().collect();
+               let auction_asks = schema.auction_asks(auction_state.id());
+               let asks = auction_asks.into_iter().collect();
+               let current_price = match auction_state.auction().auction_kind() {
+                   AuctionKind::Dutch => Some(auction_state.current_price(height)),
+                   _ => None,
+               };
+               AuctionInfo { auction_state, bids, extensions, commitments, asks, current_price }
            }))
     }
 
@@ -164,6 +409,115 @@ impl PublicApi {
         Ok(auctions)
     }
 
+    /// Products listed for sale at a fixed price.
+    fn get_listings(state: &ServiceApiState, _query: ()) -> api::Result<Vec<Listing>> {
+        let snapshot = state.snapshot();
+        let schema = schema::AuctionSchema::new(snapshot);
+        let listings = schema.listings().values().collect();
+        Ok(listings)
+    }
+
+    /// Offers made for the product.
+    fn get_product_offers(
+        state: &ServiceApiState,
+        query: ProductQuery,
+        ) -> api::Result<Vec<Offer>> {
+        let snapshot = state.snapshot();
+        let schema = schema::AuctionSchema::new(snapshot);
+        let offers = schema
+            .product_offers(&query.id)
+            .into_iter()
+            .map(|offer_id| schema.offers().get(offer_id).unwrap())
+            .collect();
+        Ok(offers)
+    }
+
+    /// Offers made by user.
+    fn get_user_offers(
+        state: &ServiceApiState,
+        query: UserQuery,
+        ) -> api::Result<Option<Vec<Offer>>> {
+        let snapshot = state.snapshot();
+        let schema = schema::AuctionSchema::new(snapshot);
+
+        Ok(schema.users().get(&query.pub_key).map(|user| {
+            let user_offers = schema.user_offers(user.pub_key());
+            let offers = user_offers
+                .into_iter()
+                .map(|offer_id| schema.offers().get(offer_id).unwrap())
+                .collect();
+            offers
+        }))
+    }
+
+    /// Order book of the product class.
+    fn get_order_book(state: &ServiceApiState, query: ClassQuery) -> api::Result<OrderBookInfo> {
+        let snapshot = state.snapshot();
+        let schema = schema::AuctionSchema::new(snapshot);
+        let class = class_id(&query.class);
+
+        let bids = schema.order_book(&class, OrderSide::Buy);
+        let asks = schema.order_book(&class, OrderSide::Sell);
+        let trades = schema.class_trades(&class).into_iter().collect();
+        Ok(OrderBookInfo { bids, asks, trades })
+    }
+
+    /// Orders placed by user.
+    fn get_user_orders(
+        state: &ServiceApiState,
+        query: UserQuery,
+        ) -> api::Result<Option<Vec<Order>>> {
+        let snapshot = state.snapshot();
+        let schema = schema::AuctionSchema::new(snapshot);
+
+        Ok(schema.users().get(&query.pub_key).map(|user| {
+            let user_orders = schema.user_orders(user.pub_key());
+            let orders = user_orders
+                .into_iter()
+                .map(|order_id| schema.orders().get(order_id).unwrap())
+                .collect();
+            orders
+        }))
+    }
+
+    /// Category taxonomy.
+    fn get_categories(state: &ServiceApiState, _query: ()) -> api::Result<Vec<Category>> {
+        let snapshot = state.snapshot();
+        let schema = schema::AuctionSchema::new(snapshot);
+        let categories = schema.categories().values().collect();
+        Ok(categories)
+    }
+
+    /// Products of the category.
+    fn get_category_products(
+        state: &ServiceApiState,
+        query: CategoryQuery,
+        ) -> api::Result<Vec<ProductState>> {
+        let snapshot = state.snapshot();
+        let schema = schema::AuctionSchema::new(snapshot);
+        let products = schema
+            .category_products(&category_id(&query.name))
+            .iter()
+            .map(|product_id| schema.products_states().get(&product_id).unwrap())
+            .collect();
+        Ok(products)
+    }
+
+    /// Open auctions of the category.
+    fn get_category_auctions(
+        state: &ServiceApiState,
+        query: CategoryQuery,
+        ) -> api::Result<Vec<AuctionState>> {
+        let snapshot = state.snapshot();
+        let schema = schema::AuctionSchema::new(snapshot);
+        let auctions = schema
+            .category_auctions(&category_id(&query.name))
+            .iter()
+            .map(|auction_id| schema.auctions().get(auction_id).unwrap())
+            .collect();
+        Ok(auctions)
+    }
+
     /// Send new transaction into the blockchain.
     fn post_transaction(state: &ServiceApiState, transaction: Transactions)-> api::Result<Hash> {
         println!("post_transaction called");
@@ -244,13 +598,27 @@ impl PublicApi {
             .public_scope()
             .endpoint("v1/users", Self::get_users)
             .endpoint("v1/user", Self::get_user)
+            .endpoint("v1/user/transfers", Self::get_user_transfers)
             .endpoint("v1/products", Self::get_products)
+            .endpoint("v1/products/search", Self::search_products)
             .endpoint("v1/product", Self::get_product)
+            .endpoint("v1/product/history", Self::get_product_history)
+            .endpoint("v1/product/barcode", Self::get_product_by_barcode)
+            .endpoint("v1/product/shares", Self::get_product_shares)
             .endpoint("v1/user/products", Self::get_user_products)
+            .endpoint("v1/product/offers", Self::get_product_offers)
+            .endpoint("v1/user/offers", Self::get_user_offers)
             .endpoint("v1/user/auctions", Self::get_users_auctions)
             .endpoint("v1/auction/bids", Self::get_auction_bids)
             .endpoint("v1/auction", Self::get_auction_with_bids)
+            .endpoint("v1/auction/info", Self::get_auction_info)
             .endpoint("v1/auctions", Self::get_auctions)
+            .endpoint("v1/categories", Self::get_categories)
+            .endpoint("v1/category/products", Self::get_category_products)
+            .endpoint("v1/category/auctions", Self::get_category_auctions)
+            .endpoint("v1/listings", Self::get_listings)
+            .endpoint("v1/orderbook", Self::get_order_book)
+            .endpoint("v1/user/orders", Self::get_user_orders)
             .endpoint_mut("v1/transaction", Self::post_transaction)
             .endpoint_mut("v1/sync_transaction", Self::post_sync_transaction);
     }
@@ -2,15 +2,56 @@
 
 use exonum::crypto::{Hash, PublicKey};
 
+use CANCEL_PENALTY_PERCENT;
+
 encoding_struct! {
     /// Information about auction.
     struct Auction {
-        /// User selling property.
+        /// User selling property. Buyer in reverse auctions.
         public_key: &PublicKey,
-        /// Property with 'product_id' is auctioned.
-        product_id: &Hash,
-        /// Start price for auction.
+        /// Properties with 'product_ids' are auctioned. Units of a multi-unit auction, otherwise
+        /// the bundle passed to the winner at once.
+        product_ids: Vec<Hash>,
+        /// Start price for auction. Maximum budget of the buyer in reverse auctions.
         start_price: u64,
+        /// Auction duration in blocks.
+        duration: u64,
+        /// Bids made in the last 'extension_window' blocks extend the auction.
+        extension_window: u64,
+        /// Number of blocks the auction is extended by.
+        extension: u64,
+        /// Auction kind, see `AuctionKind`.
+        kind: u8,
+        /// Dutch auction: price drop per block.
+        price_step: u64,
+        /// Dutch auction: price can't drop below this value.
+        floor_price: u64,
+        /// Sealed auction: number of blocks after bidding ends during which bids are revealed.
+        reveal_duration: u64,
+        /// Sealed auction: deposit reserved with each bid commitment.
+        deposit: u64,
+        /// Settlement rule, see `Settlement`.
+        settlement: u8,
+        /// Hash of the `SealedReserve`, zero hash if the auction has no reserve price.
+        reserve_hash: &Hash,
+        /// Price ending the auction instantly, zero if not available.
+        buy_now_price: u64,
+        /// Minimum bid increment rule, see `IncrementRule`.
+        increment_rule: u8,
+        /// Absolute or percentage increment.
+        increment: u64,
+        /// Tiered increments sorted by price band.
+        increment_tiers: Vec<IncrementTier>,
+    }
+}
+
+encoding_struct! {
+    /// Minimum bid increment for the price band starting at 'from_price'.
+    struct IncrementTier {
+        /// Lower bound of the price band.
+        from_price: u64,
+        /// Minimum increment in the band.
+        increment: u64,
     }
 }
 
@@ -21,10 +62,22 @@ encoding_struct! {
         id: u64,
         /// Auction information.
         auction: Auction,
-        /// Merkle root of history of bids. Last bid wins.
+        /// Merkle root of history of bids (asks in reverse auctions). Last bid wins.
         bidding_merkle_root: &Hash,
         /// Closed flag. If closed then no bids are allowed.
         closed: bool,
+        /// Height of the block at which bidding ends.
+        ends_at: u64,
+        /// Merkle root of history of deadline extensions.
+        extensions_merkle_root: &Hash,
+        /// Height of the block at which the auction was created.
+        started_at: u64,
+        /// Reserve price revealed by the seller.
+        reserve: u64,
+        /// Reserve price revealed flag.
+        reserve_revealed: bool,
+        /// Cancelled flag. Cancelled auction is closed without a winner.
+        cancelled: bool,
     }
 }
 
@@ -33,7 +86,303 @@ encoding_struct! {
     struct Bid {
         /// public_key is users Bidder is some participant identified by their public key.
         public_key: &PublicKey,
+        /// Value of the bid. Unit price in multi-unit auctions.
+        value: u64,
+        /// Number of units, 1 for single product auctions.
+        quantity: u64,
+    }
+}
+
+/// Auction kinds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AuctionKind {
+    /// Ascending price auction. The highest bid wins at close.
+    English = 0,
+    /// Descending price auction. The first bid at the current price wins immediately.
+    Dutch = 1,
+    /// Sealed bid auction. Bids are committed as hashes and revealed after bidding ends.
+    Sealed = 2,
+    /// Auction of identical units. Bids with the highest unit price win the units at close.
+    MultiUnit = 3,
+    /// Procurement auction. Sellers offer their products asking descending prices, the lowest
+    /// ask wins at close.
+    Reverse = 4,
+}
+
+impl AuctionKind {
+    /// Converts stored auction kind value.
+    pub fn from_u8(value: u8) -> Option<AuctionKind> {
+        match value {
+            0 => Some(AuctionKind::English),
+            1 => Some(AuctionKind::Dutch),
+            2 => Some(AuctionKind::Sealed),
+            3 => Some(AuctionKind::MultiUnit),
+            4 => Some(AuctionKind::Reverse),
+            _ => None,
+        }
+    }
+}
+
+/// Settlement rules defining the price paid by the winner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Settlement {
+    /// The winner pays the value of its bid.
+    FirstPrice = 0,
+    /// The winner pays the second highest bid or the start price (Vickrey auction).
+    SecondPrice = 1,
+    /// Multi-unit auction winners pay the lowest winning unit price.
+    Uniform = 2,
+}
+
+impl Settlement {
+    /// Converts stored settlement rule value.
+    pub fn from_u8(value: u8) -> Option<Settlement> {
+        match value {
+            0 => Some(Settlement::FirstPrice),
+            1 => Some(Settlement::SecondPrice),
+            2 => Some(Settlement::Uniform),
+            _ => None,
+        }
+    }
+}
+
+/// Minimum bid increment rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum IncrementRule {
+    /// Any bid above the highest bid is accepted.
+    Any = 0,
+    /// Fixed increment.
+    Absolute = 1,
+    /// Increment in percent of the highest bid.
+    Percent = 2,
+    /// Increment depends on the price band of the highest bid.
+    Tiered = 3,
+}
+
+impl IncrementRule {
+    /// Converts stored increment rule value.
+    pub fn from_u8(value: u8) -> Option<IncrementRule> {
+        match value {
+            0 => Some(IncrementRule::Any),
+            1 => Some(IncrementRule::Absolute),
+            2 => Some(IncrementRule::Percent),
+            3 => Some(IncrementRule::Tiered),
+            _ => None,
+        }
+    }
+}
+
+impl Auction {
+    /// Kind of the auction.
+    pub fn auction_kind(&self) -> AuctionKind {
+        AuctionKind::from_u8(self.kind()).expect("Auction kind should be valid.")
+    }
+
+    /// Settlement rule of the auction.
+    pub fn settlement_rule(&self) -> Settlement {
+        Settlement::from_u8(self.settlement()).expect("Settlement rule should be valid.")
+    }
+
+    /// Minimum bid increment rule of the auction.
+    pub fn increment_rule_kind(&self) -> IncrementRule {
+        IncrementRule::from_u8(self.increment_rule()).expect("Increment rule should be valid.")
+    }
+
+    /// Checks if increment parameters are consistent.
+    pub fn has_valid_increment(&self) -> bool {
+        match IncrementRule::from_u8(self.increment_rule()) {
+            Some(IncrementRule::Any) => true,
+            Some(IncrementRule::Absolute) | Some(IncrementRule::Percent) => self.increment() > 0,
+            Some(IncrementRule::Tiered) => {
+                let tiers = self.increment_tiers();
+                !tiers.is_empty()
+                    && tiers.windows(2).all(|w| w[0].from_price() < w[1].from_price())
+            }
+            None => false,
+        }
+    }
+
+    /// Minimum bid accepted after the highest bid with the given value. The first bid must
+    /// reach the start price only.
+    pub fn min_bid(&self, highest_bid: Option<u64>) -> u64 {
+        highest_bid.map_or(self.start_price(), |value| self.min_next_bid(value))
+    }
+
+    /// Minimum bid accepted after the bid with the given value.
+    pub fn min_next_bid(&self, value: u64) -> u64 {
+        let increment = match self.increment_rule_kind() {
+            IncrementRule::Any => 0,
+            IncrementRule::Absolute => self.increment(),
+            IncrementRule::Percent => {
+                let increment = u128::from(value) * u128::from(self.increment()) / 100;
+                increment.min(u128::from(u64::max_value())) as u64
+            }
+            IncrementRule::Tiered => self
+                .increment_tiers()
+                .iter()
+                .take_while(|tier| tier.from_price() <= value)
+                .last()
+                .map_or(0, |tier| tier.increment()),
+        };
+        value.saturating_add(increment.max(1))
+    }
+
+    /// Checks if the seller has committed a reserve price.
+    pub fn has_reserve(&self) -> bool {
+        *self.reserve_hash() != Hash::zero()
+    }
+}
+
+/// Penalty paid by the seller backing out of the auction with the given highest bid or lowest ask.
+pub fn cancel_penalty(value: u64) -> u64 {
+    (u128::from(value) * u128::from(CANCEL_PENALTY_PERCENT) / 100) as u64
+}
+
+impl AuctionState {
+    /// Current price of the auction at the given height. Changes over time for Dutch auctions only.
+    pub fn current_price(&self, height: u64) -> u64 {
+        let auction = self.auction();
+        match auction.auction_kind() {
+            AuctionKind::English
+            | AuctionKind::Sealed
+            | AuctionKind::MultiUnit
+            | AuctionKind::Reverse => auction.start_price(),
+            AuctionKind::Dutch => {
+                let elapsed = height.saturating_sub(self.started_at());
+                let price = auction
+                    .start_price()
+                    .saturating_sub(auction.price_step().saturating_mul(elapsed));
+                price.max(auction.floor_price())
+            }
+        }
+    }
+
+    /// Height of the block from which the auction may be closed.
+    pub fn closes_at(&self) -> u64 {
+        let auction = self.auction();
+        if auction.auction_kind() == AuctionKind::Sealed || auction.has_reserve() {
+            self.ends_at() + auction.reveal_duration()
+        } else {
+            self.ends_at()
+        }
+    }
+
+    /// Checks if the bid value satisfies the reserve price. Reserve price that was committed
+    /// but not revealed by the seller is never met.
+    pub fn reserve_met(&self, value: u64) -> bool {
+        !self.auction().has_reserve() || (self.reserve_revealed() && value >= self.reserve())
+    }
+
+    /// Returns closed copy of the auction state.
+    pub fn close(&self) -> AuctionState {
+        AuctionState::new(
+            self.id(),
+            self.auction(),
+            self.bidding_merkle_root(),
+            true,
+            self.ends_at(),
+            self.extensions_merkle_root(),
+            self.started_at(),
+            self.reserve(),
+            self.reserve_revealed(),
+            self.cancelled(),
+        )
+    }
+
+    /// Returns cancelled copy of the auction state.
+    pub fn cancel(&self) -> AuctionState {
+        AuctionState::new(
+            self.id(),
+            self.auction(),
+            self.bidding_merkle_root(),
+            true,
+            self.ends_at(),
+            self.extensions_merkle_root(),
+            self.started_at(),
+            self.reserve(),
+            self.reserve_revealed(),
+            true,
+        )
+    }
+}
+
+encoding_struct! {
+    /// Ask of the seller in a reverse auction.
+    struct Ask {
+        /// Seller.
+        public_key: &PublicKey,
+        /// Product offered to the buyer.
+        product_id: &Hash,
+        /// Asked price.
+        value: u64,
+    }
+}
+
+encoding_struct! {
+    /// Auction deadline extension caused by a late bid.
+    struct DeadlineExtension {
+        /// Bidder who caused the extension.
+        public_key: &PublicKey,
+        /// Height at which the bid was made.
+        height: u64,
+        /// New end of the bidding period.
+        ends_at: u64,
+    }
+}
+
+encoding_struct! {
+    /// Sealed bid. Hash of this structure is committed during the bidding period.
+    struct SealedBid {
+        /// Bidder.
+        public_key: &PublicKey,
+        /// Auction ID.
+        auction_id: u64,
         /// Value of the bid.
         value: u64,
+        /// Random salt hiding the value.
+        salt: &Hash,
+    }
+}
+
+encoding_struct! {
+    /// Sealed bid commitment.
+    struct BidCommitment {
+        /// Bidder.
+        public_key: &PublicKey,
+        /// Hash of the `SealedBid`.
+        commitment: &Hash,
+        /// Deposit reserved with the commitment.
+        deposit: u64,
+        /// Revealed flag.
+        revealed: bool,
+        /// Revealed value of the bid.
+        value: u64,
+    }
+}
+
+encoding_struct! {
+    /// Sealed reserve price. Hash of this structure is committed on auction creation.
+    struct SealedReserve {
+        /// Seller.
+        public_key: &PublicKey,
+        /// Reserve price.
+        value: u64,
+        /// Random salt hiding the value.
+        salt: &Hash,
+    }
+}
+
+encoding_struct! {
+    /// Proxy bid. The system bids on behalf of the user up to the maximum.
+    struct ProxyBid {
+        /// Bidder.
+        public_key: &PublicKey,
+        /// Maximum value of the bids.
+        max_value: u64,
+        /// Active flag. Proxy bid is deactivated once outbid or the auction is closed.
+        active: bool,
     }
 }
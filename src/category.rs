@@ -0,0 +1,20 @@
+//! Product category structures definition.
+
+use exonum::crypto::{self, Hash};
+
+encoding_struct! {
+    /// Category of the taxonomy maintained by administrators.
+    struct Category {
+        /// Category name, unique in the taxonomy.
+        name: &str,
+        /// Name of the parent category, empty for top level categories.
+        parent: &str,
+        /// Category description.
+        description: &str,
+    }
+}
+
+/// Identifier of the category.
+pub fn category_id(name: &str) -> Hash {
+    crypto::hash(name.as_bytes())
+}
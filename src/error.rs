@@ -3,45 +3,211 @@
 use exonum::blockchain::ExecutionError;
 
 #[derive(Debug, Fail)]
-#[repr(u8)]
 pub enum Error {
     #[fail(display = "Insufficient funds.")]
-    InsufficientFunds = 1,
+    InsufficientFunds,
 
     #[fail(display = "User is already registered")]
-    UserAlreadyRegistered = 2,
+    UserAlreadyRegistered,
 
     #[fail(display = "Participant is not registered")]
-    UserIsNotRegistered = 3,
+    UserIsNotRegistered,
 
     #[fail(display = "Product does not exist")]
-    ProductNotFound = 4,
+    ProductNotFound,
 
     #[fail(display = "You do not own of the item")]
-    ProductNotOwned = 5,
+    ProductNotOwned,
 
-    #[fail(display = "Product is already auctioned")]
-    ProductAlreadyAuctioned = 6,
+    #[fail(display = "Product is already auctioned or offered for sale")]
+    ProductAlreadyAuctioned,
 
     #[fail(display = "Auction does not exist")]
-    AuctionNotFound = 7,
+    AuctionNotFound,
 
     #[fail(display = "Auction is closed")]
-    AuctionClosed = 8,
+    AuctionClosed,
 
     #[fail(display = "Bid is below the current highest bid")]
-    BidTooLow = 9,
+    BidTooLow,
     // CloseAuction can only be performed by the validator nodes.
     #[fail(display = "Transaction is not authorized.")]
-    UnauthorizedTransaction = 10,
+    UnauthorizedTransaction,
 
     #[fail(display = "You may not bid on your own item.")]
-    NoSelfBidding = 11,
+    NoSelfBidding,
+
+    #[fail(display = "Invalid auction parameters")]
+    InvalidAuctionParameters,
+
+    #[fail(display = "Bidding period is over")]
+    BiddingEnded,
+
+    #[fail(display = "Auction is still in progress")]
+    AuctionNotEnded,
+
+    #[fail(display = "Operation is not supported by the auction kind")]
+    WrongAuctionKind,
+
+    #[fail(display = "Bid is already committed")]
+    AlreadyCommitted,
+
+    #[fail(display = "Commitment does not exist")]
+    CommitmentNotFound,
+
+    #[fail(display = "Commitment is already revealed")]
+    AlreadyRevealed,
+
+    #[fail(display = "Revealed value does not match the commitment")]
+    CommitmentMismatch,
+
+    #[fail(display = "Commitments can't be revealed now")]
+    NotRevealPeriod,
+
+    #[fail(display = "Buy now is not available")]
+    BuyNowUnavailable,
+
+    #[fail(display = "Auction can't be cancelled")]
+    CancellationNotAllowed,
+
+    #[fail(display = "Bid is below the minimum required bid {}", minimum)]
+    BidBelowMinimum { minimum: u64 },
+
+    #[fail(display = "You have an active proxy bid in the auction")]
+    ProxyBidActive,
+
+    #[fail(display = "Invalid bid quantity")]
+    InvalidBidQuantity,
+
+    #[fail(display = "Ask is not below the lowest ask")]
+    AskTooHigh,
+
+    #[fail(display = "Invalid order parameters")]
+    InvalidOrderParameters,
+
+    #[fail(display = "Order does not exist")]
+    OrderNotFound,
+
+    #[fail(display = "Order is not open")]
+    OrderClosed,
+
+    #[fail(display = "Product is not listed for sale")]
+    ListingNotFound,
+
+    #[fail(display = "Listing price does not match")]
+    ListingPriceMismatch,
+
+    #[fail(display = "Price must be positive")]
+    ZeroPrice,
+
+    #[fail(display = "Offer does not exist")]
+    OfferNotFound,
+
+    #[fail(display = "Offer is not pending")]
+    OfferClosed,
+
+    #[fail(display = "Offer is expired")]
+    OfferExpired,
+
+    #[fail(display = "Offer is not expired yet")]
+    OfferNotExpired,
+
+    #[fail(display = "Amount must be positive")]
+    ZeroAmount,
+
+    #[fail(display = "Transfer to self is not allowed")]
+    SelfTransfer,
+
+    #[fail(display = "Product is retired")]
+    ProductRetired,
+
+    #[fail(display = "Invalid product parameters")]
+    InvalidProductParameters,
+
+    #[fail(display = "Barcode is already used")]
+    BarcodeAlreadyUsed,
+
+    #[fail(display = "Category does not exist")]
+    CategoryNotFound,
+
+    #[fail(display = "Category already exists")]
+    CategoryAlreadyExists,
+
+    #[fail(display = "Category is in use")]
+    CategoryInUse,
+
+    #[fail(display = "Category name must not be empty")]
+    InvalidCategoryName,
+
+    #[fail(display = "Product is split into shares")]
+    ProductSplit,
+
+    #[fail(display = "Insufficient shares")]
+    InsufficientShares,
+
+    #[fail(display = "Invalid number of shares")]
+    InvalidShares,
+}
+
+impl Error {
+    /// Error code reported in the transaction execution result.
+    pub fn code(&self) -> u8 {
+        match *self {
+            Error::InsufficientFunds => 1,
+            Error::UserAlreadyRegistered => 2,
+            Error::UserIsNotRegistered => 3,
+            Error::ProductNotFound => 4,
+            Error::ProductNotOwned => 5,
+            Error::ProductAlreadyAuctioned => 6,
+            Error::AuctionNotFound => 7,
+            Error::AuctionClosed => 8,
+            Error::BidTooLow => 9,
+            Error::UnauthorizedTransaction => 10,
+            Error::NoSelfBidding => 11,
+            Error::InvalidAuctionParameters => 12,
+            Error::BiddingEnded => 13,
+            Error::AuctionNotEnded => 14,
+            Error::WrongAuctionKind => 15,
+            Error::AlreadyCommitted => 16,
+            Error::CommitmentNotFound => 17,
+            Error::AlreadyRevealed => 18,
+            Error::CommitmentMismatch => 19,
+            Error::NotRevealPeriod => 20,
+            Error::BuyNowUnavailable => 21,
+            Error::CancellationNotAllowed => 22,
+            Error::BidBelowMinimum { .. } => 23,
+            Error::ProxyBidActive => 24,
+            Error::InvalidBidQuantity => 25,
+            Error::AskTooHigh => 26,
+            Error::InvalidOrderParameters => 27,
+            Error::OrderNotFound => 28,
+            Error::OrderClosed => 29,
+            Error::ListingNotFound => 30,
+            Error::ListingPriceMismatch => 31,
+            Error::ZeroPrice => 32,
+            Error::OfferNotFound => 33,
+            Error::OfferClosed => 34,
+            Error::OfferExpired => 35,
+            Error::SelfTransfer => 36,
+            Error::ProductRetired => 37,
+            Error::InvalidProductParameters => 38,
+            Error::BarcodeAlreadyUsed => 39,
+            Error::CategoryNotFound => 40,
+            Error::CategoryAlreadyExists => 41,
+            Error::CategoryInUse => 42,
+            Error::ProductSplit => 43,
+            Error::InsufficientShares => 44,
+            Error::InvalidShares => 45,
+            Error::OfferNotExpired => 46,
+            Error::ZeroAmount => 47,
+            Error::InvalidCategoryName => 48,
+        }
+    }
 }
 
 impl From<Error> for ExecutionError {
     fn from(value: Error) -> ExecutionError {
         let description = format!("{}", value);
-        ExecutionError::with_description(value as u8, description)
+        ExecutionError::with_description(value.code(), description)
     }
 }
@@ -13,6 +13,8 @@ extern crate serde;
 extern crate serde_derive;
 #[macro_use]
 extern crate lazy_static;
+#[macro_use]
+extern crate log;
 
 
 pub use schema::AuctionSchema;
@@ -23,18 +25,23 @@ pub mod transactions;
 pub mod user;
 pub mod auction;
 pub mod product;
+pub mod order;
+pub mod listing;
+pub mod offer;
+pub mod category;
+pub mod share;
 pub mod error;
 pub mod static_channel;
 
 use exonum::{
-    api::ServiceApiBuilder, blockchain::{self, Transaction, TransactionSet},
+    api::ServiceApiBuilder, blockchain::{self, ServiceContext, Transaction, TransactionSet},
     crypto::Hash,
     encoding::Error as EncodingError,
     helpers::fabric::{self, Context}, messages::RawTransaction,
-    storage::Snapshot,
+    node::TransactionSend, storage::Snapshot,
 };
 
-use transactions::Transactions;
+use transactions::{CloseAuction, ExpireOffer, Transactions};
 
 /// Unique service ID.
 const AUCTION_SERVICE_ID: u16 = 73;
@@ -42,6 +49,12 @@ const AUCTION_SERVICE_ID: u16 = 73;
 const SERVICE_NAME: &str = "auction";
 /// Initial balance of the wallet.
 const INITIAL_BALANCE: u64 = 100;
+/// Prefix of barcodes generated for products created without a barcode.
+const AUTO_BARCODE_PREFIX: &str = "AUTO-";
+/// Maximum royalty paid to the product creator, in percent of the winning price.
+const MAX_ROYALTY_PERCENT: u64 = 50;
+/// Penalty paid by the seller to the highest bidder on auction cancellation, in percent of the bid.
+const CANCEL_PENALTY_PERCENT: u64 = 10;
 
 
 /// Exonum `Service` implementation.
@@ -69,9 +82,43 @@ impl blockchain::Service for Service {
         Ok(tx.into())
     }
 
-    //fn after_commit(&self, context: &ServiceContext) {
-    //    static_channel::notify();
-    //}
+    // The leader of the first round of the next height closes auctions whose bidding
+    // (and revealing) period is over and expires offers, so that each close is broadcast
+    // by a single validator. `context.height()` is the height of the committed block, rounds
+    // of the next height start from 1.
+    fn after_commit(&self, context: &ServiceContext) {
+        let validators = context.actual_configuration().validator_keys.len() as u64;
+        let leader = (context.height().0 + 2) % validators;
+        if context.validator_id().map(|id| u64::from(id.0)) != Some(leader) {
+            return;
+        }
+
+        let schema = AuctionSchema::new(context.snapshot());
+        let height = schema.height();
+        for auction_id in schema.open_auctions().iter() {
+            let auction_state = schema.auctions().get(auction_id).unwrap();
+            if auction_state.closes_at() > height {
+                continue;
+            }
+
+            let tx = CloseAuction::new(auction_id, context.public_key(), context.secret_key());
+            if let Err(e) = context.transaction_sender().send(Box::new(tx)) {
+                error!("Failed to send CloseAuction transaction: {}", e);
+            }
+        }
+
+        for offer_id in schema.pending_offers().iter() {
+            let offer = schema.offers().get(offer_id).unwrap();
+            if offer.expires_at() > height {
+                continue;
+            }
+
+            let tx = ExpireOffer::new(offer_id, context.public_key(), context.secret_key());
+            if let Err(e) = context.transaction_sender().send(Box::new(tx)) {
+                error!("Failed to send ExpireOffer transaction: {}", e);
+            }
+        }
+    }
 
     fn wire_api(&self, builder: &mut ServiceApiBuilder) {
         api::PublicApi::wire(builder);
@@ -0,0 +1,17 @@
+//! Fixed-price listing structures definition.
+
+use exonum::crypto::{Hash, PublicKey};
+
+encoding_struct! {
+    /// Product listed for sale at a fixed price.
+    struct Listing {
+        /// Seller.
+        public_key: &PublicKey,
+        /// Listed product.
+        product_id: &Hash,
+        /// Price of the product.
+        price: u64,
+        /// Height of the block at which the product was listed.
+        listed_at: u64,
+    }
+}
@@ -0,0 +1,70 @@
+//! Private offer structures definition.
+
+use exonum::crypto::{Hash, PublicKey};
+
+encoding_struct! {
+    /// Offer to buy the product from its owner.
+    struct Offer {
+        /// Offer id.
+        id: u64,
+        /// User making the offer.
+        public_key: &PublicKey,
+        /// Product the offer is made for.
+        product_id: &Hash,
+        /// Offered price, reserved in the wallet of the user.
+        value: u64,
+        /// Height of the block from which the offer can't be accepted.
+        expires_at: u64,
+        /// Offer status, see `OfferStatus`.
+        status: u8,
+    }
+}
+
+/// Offer statuses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum OfferStatus {
+    /// Offer awaits the decision of the owner.
+    Pending = 0,
+    /// Offer is accepted, the product is sold.
+    Accepted = 1,
+    /// Offer is rejected by the owner.
+    Rejected = 2,
+    /// Offer is withdrawn by the user who made it.
+    Withdrawn = 3,
+    /// Offer is expired and closed by the validator nodes.
+    Expired = 4,
+}
+
+impl OfferStatus {
+    /// Converts stored offer status value.
+    pub fn from_u8(value: u8) -> Option<OfferStatus> {
+        match value {
+            0 => Some(OfferStatus::Pending),
+            1 => Some(OfferStatus::Accepted),
+            2 => Some(OfferStatus::Rejected),
+            3 => Some(OfferStatus::Withdrawn),
+            4 => Some(OfferStatus::Expired),
+            _ => None,
+        }
+    }
+}
+
+impl Offer {
+    /// Status of the offer.
+    pub fn offer_status(&self) -> OfferStatus {
+        OfferStatus::from_u8(self.status()).expect("Offer status should be valid.")
+    }
+
+    /// Returns copy of the offer with the given status.
+    pub fn with_status(&self, status: OfferStatus) -> Offer {
+        Offer::new(
+            self.id(),
+            self.public_key(),
+            self.product_id(),
+            self.value(),
+            self.expires_at(),
+            status as u8,
+        )
+    }
+}
@@ -0,0 +1,147 @@
+//! Order book structures definition.
+
+use exonum::crypto::{self, Hash, PublicKey};
+
+encoding_struct! {
+    /// Limit order for one product of the class.
+    struct Order {
+        /// Order id.
+        id: u64,
+        /// User placing the order.
+        public_key: &PublicKey,
+        /// Product class, products with the same name belong to the same class.
+        class: &str,
+        /// Order side, see `OrderSide`.
+        side: u8,
+        /// Product offered by the sell order, zero hash for buy orders.
+        product_id: &Hash,
+        /// Limit price.
+        price: u64,
+        /// Height of the block at which the order was placed.
+        placed_at: u64,
+        /// Order status, see `OrderStatus`.
+        status: u8,
+    }
+}
+
+encoding_struct! {
+    /// Trade of the matched buy and sell orders.
+    struct Trade {
+        /// Buy order id.
+        buy_order: u64,
+        /// Sell order id.
+        sell_order: u64,
+        /// Traded product.
+        product_id: &Hash,
+        /// Price paid by the buyer.
+        price: u64,
+        /// Height of the block at which the orders were matched.
+        height: u64,
+    }
+}
+
+/// Order sides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum OrderSide {
+    /// Bid to buy a product of the class. The price is reserved in the buyer wallet.
+    Buy = 0,
+    /// Ask to sell the product. The product is locked until the order is filled or cancelled.
+    Sell = 1,
+}
+
+impl OrderSide {
+    /// Converts stored order side value.
+    pub fn from_u8(value: u8) -> Option<OrderSide> {
+        match value {
+            0 => Some(OrderSide::Buy),
+            1 => Some(OrderSide::Sell),
+            _ => None,
+        }
+    }
+
+    /// Side of the orders matched against the orders of this side.
+    pub fn opposite(self) -> OrderSide {
+        match self {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        }
+    }
+}
+
+/// Order statuses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum OrderStatus {
+    /// Order rests in the order book.
+    Open = 0,
+    /// Order is matched.
+    Filled = 1,
+    /// Order is cancelled by its owner.
+    Cancelled = 2,
+}
+
+impl OrderStatus {
+    /// Converts stored order status value.
+    pub fn from_u8(value: u8) -> Option<OrderStatus> {
+        match value {
+            0 => Some(OrderStatus::Open),
+            1 => Some(OrderStatus::Filled),
+            2 => Some(OrderStatus::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+/// Identifier of the product class.
+pub fn class_id(class: &str) -> Hash {
+    crypto::hash(class.as_bytes())
+}
+
+impl Order {
+    /// Side of the order.
+    pub fn order_side(&self) -> OrderSide {
+        OrderSide::from_u8(self.side()).expect("Order side should be valid.")
+    }
+
+    /// Status of the order.
+    pub fn order_status(&self) -> OrderStatus {
+        OrderStatus::from_u8(self.status()).expect("Order status should be valid.")
+    }
+
+    /// Checks if the order can be matched with the resting order at its price.
+    pub fn crosses(&self, resting: &Order) -> bool {
+        match self.order_side() {
+            OrderSide::Buy => resting.price() <= self.price(),
+            OrderSide::Sell => resting.price() >= self.price(),
+        }
+    }
+
+    /// Key of the order in the order book. Keys are ordered by price priority (the lowest
+    /// price for sell orders, the highest one for buy orders) and then by time of placement.
+    pub fn book_key(&self) -> Vec<u8> {
+        let priority = match self.order_side() {
+            OrderSide::Buy => u64::max_value() - self.price(),
+            OrderSide::Sell => self.price(),
+        };
+        let mut key = Vec::with_capacity(16);
+        for value in &[priority, self.id()] {
+            key.extend((0..8).rev().map(|i| (value >> (i * 8)) as u8));
+        }
+        key
+    }
+
+    /// Returns copy of the order with the given status.
+    pub fn with_status(&self, status: OrderStatus) -> Order {
+        Order::new(
+            self.id(),
+            self.public_key(),
+            self.class(),
+            self.side(),
+            self.product_id(),
+            self.price(),
+            self.placed_at(),
+            status as u8,
+        )
+    }
+}
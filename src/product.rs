@@ -1,14 +1,71 @@
 //! Product (i.e. goods) structures definition.
 
-use exonum::crypto::PublicKey;
+use exonum::crypto::{Hash, PublicKey};
 
 encoding_struct! {
     /// Product structure. Hash of this structure is its unique id.
     struct Product {
         /// Product name.
         name: &str,
-        /// Product barcode.
-        barcode: u64,
+        /// Product barcode, GTIN or serial number. Unique among all products.
+        barcode: &str,
+        /// Product description.
+        description: &str,
+        /// Product category.
+        category: &str,
+        /// Product condition, see `ProductCondition`.
+        condition: u8,
+        /// Hash of the off-chain image or document, zero hash if there is none.
+        content_hash: &Hash,
+        /// Arbitrary attributes with distinct keys.
+        attributes: Vec<ProductAttribute>,
+    }
+}
+
+encoding_struct! {
+    /// Product attribute.
+    struct ProductAttribute {
+        /// Attribute name.
+        key: &str,
+        /// Attribute value.
+        value: &str,
+    }
+}
+
+/// Product conditions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ProductCondition {
+    /// Brand new product.
+    New = 0,
+    /// Used product in good condition.
+    Used = 1,
+    /// Product restored by the manufacturer or the seller.
+    Refurbished = 2,
+    /// Damaged product sold for parts.
+    ForParts = 3,
+}
+
+impl ProductCondition {
+    /// Converts stored product condition value.
+    pub fn from_u8(value: u8) -> Option<ProductCondition> {
+        match value {
+            0 => Some(ProductCondition::New),
+            1 => Some(ProductCondition::Used),
+            2 => Some(ProductCondition::Refurbished),
+            3 => Some(ProductCondition::ForParts),
+            _ => None,
+        }
+    }
+}
+
+impl Product {
+    /// Value of the attribute with the given key.
+    pub fn attribute(&self, key: &str) -> Option<String> {
+        self.attributes()
+            .into_iter()
+            .find(|attribute| attribute.key() == key)
+            .map(|attribute| attribute.value().to_string())
     }
 }
 
@@ -17,7 +74,98 @@ encoding_struct! {
     struct ProductState {
         /// Product.
         product: Product,
-        /// Product owner.
+        /// Product owner. The last owner of the retired product.
         owner: &PublicKey,
+        /// Retired flag. Retired product is destroyed or redeemed and can't be traded.
+        retired: bool,
+        /// Merkle root of history of ownership changes.
+        history_merkle_root: &Hash,
+        /// Number of shares the product is split into, zero if it is not split.
+        shares: u64,
+        /// User who created the product.
+        creator: &PublicKey,
+        /// Royalty paid to the creator on auction resale, in percent of the price.
+        royalty_percent: u64,
+    }
+}
+
+encoding_struct! {
+    /// Change of the product ownership.
+    struct OwnershipRecord {
+        /// Kind of the change, see `OwnershipChange`.
+        kind: u8,
+        /// Previous owner, the creator for product creation. Previous holder of the shares for
+        /// share transfers and sales.
+        from: &PublicKey,
+        /// New owner. New holder of the shares for share transfers and sales.
+        to: &PublicKey,
+        /// Auction id for auction sales. Zero is stored for other kinds of changes, so it is
+        /// meaningful only together with 'kind', see `sale_auction`.
+        auction_id: u64,
+        /// Price paid for the product, zero for creation, transfers and retirement. Price of
+        /// the whole lot for products sold in bundle, total price of shares for share sales.
+        price: u64,
+        /// Number of shares passed for share transfers and sales, zero for changes of the whole
+        /// product.
+        shares: u64,
+        /// Height of the block at which the ownership was changed.
+        height: u64,
+    }
+}
+
+/// Kinds of ownership changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum OwnershipChange {
+    /// Product is created.
+    Creation = 0,
+    /// Product is sold in the auction.
+    AuctionSale = 1,
+    /// Product is handed to another user.
+    Transfer = 2,
+    /// Product is sold in the order book.
+    Trade = 3,
+    /// Product is bought at the fixed price.
+    ListingSale = 4,
+    /// Product is sold by accepting the offer.
+    OfferSale = 5,
+    /// Product is retired by the owner.
+    Retirement = 6,
+    /// Shares of the split product are handed to another user.
+    ShareTransfer = 7,
+    /// Shares of the split product are bought at the offered price.
+    ShareSale = 8,
+}
+
+impl OwnershipChange {
+    /// Converts stored ownership change value.
+    pub fn from_u8(value: u8) -> Option<OwnershipChange> {
+        match value {
+            0 => Some(OwnershipChange::Creation),
+            1 => Some(OwnershipChange::AuctionSale),
+            2 => Some(OwnershipChange::Transfer),
+            3 => Some(OwnershipChange::Trade),
+            4 => Some(OwnershipChange::ListingSale),
+            5 => Some(OwnershipChange::OfferSale),
+            6 => Some(OwnershipChange::Retirement),
+            7 => Some(OwnershipChange::ShareTransfer),
+            8 => Some(OwnershipChange::ShareSale),
+            _ => None,
+        }
+    }
+}
+
+impl OwnershipRecord {
+    /// Kind of the change.
+    pub fn change(&self) -> OwnershipChange {
+        OwnershipChange::from_u8(self.kind()).expect("Ownership change should be valid.")
+    }
+
+    /// Auction the product was sold in, `None` if the change is not an auction sale.
+    pub fn sale_auction(&self) -> Option<u64> {
+        match self.change() {
+            OwnershipChange::AuctionSale => Some(self.auction_id()),
+            _ => None,
+        }
     }
 }
@@ -1,15 +1,30 @@
 //! Auction database schema.
 
+use std::collections::BTreeSet;
+
 use exonum::{
+    blockchain::Schema,
     crypto::{CryptoHash, Hash, PublicKey},
-    storage::{Fork, ProofListIndex, ProofMapIndex, Snapshot, ValueSetIndex, ListIndex, MapIndex},
+    storage::{Entry, Fork, ProofListIndex, ProofMapIndex, Snapshot, ValueSetIndex, ListIndex, MapIndex, KeySetIndex},
 };
 
-use user::User;
+use user::{TransferRecord, User};
 use product::Product;
-use product::ProductState; 
-use auction::AuctionState;
+use product::{OwnershipChange, OwnershipRecord, ProductState};
+use auction::{cancel_penalty, AuctionKind, AuctionState, Settlement};
+use auction::Ask;
 use auction::Bid;
+use auction::BidCommitment;
+use auction::DeadlineExtension;
+use auction::ProxyBid;
+use category::{category_id, Category};
+use listing::Listing;
+use offer::{Offer, OfferStatus};
+use share::ShareListing;
+use order::{class_id, Order, OrderSide, OrderStatus, Trade};
+use error::Error;
+
+use AUTO_BARCODE_PREFIX;
 
 #[derive(Debug)]
 pub struct AuctionSchema<T> {
@@ -36,6 +51,46 @@ T: AsRef<dyn Snapshot>,
         ProofMapIndex::new("auction.products_states", &self.view)
     }
 
+    /// Table for linking barcode and its product.
+    pub fn barcodes(&self) -> MapIndex<&T, String, Hash> {
+        MapIndex::new("auction.barcodes", &self.view)
+    }
+
+    /// Number of barcodes generated for products created without a barcode.
+    pub fn barcode_sequence(&self) -> Entry<&T, u64> {
+        Entry::new("auction.barcode_sequence", &self.view)
+    }
+
+    /// Product categories by their ids.
+    pub fn categories(&self) -> ProofMapIndex<&T, Hash, Category> {
+        ProofMapIndex::new("auction.categories", &self.view)
+    }
+
+    /// Products of the category.
+    pub fn category_products(&self, category_id: &Hash) -> KeySetIndex<&T, Hash> {
+        KeySetIndex::new_in_family("auction.category_products", category_id, &self.view)
+    }
+
+    /// Open auctions of products of the category.
+    pub fn category_auctions(&self, category_id: &Hash) -> KeySetIndex<&T, u64> {
+        KeySetIndex::new_in_family("auction.category_auctions", category_id, &self.view)
+    }
+
+    /// Shares of the split product by their holders.
+    pub fn product_shares(&self, product_id: &Hash) -> ProofMapIndex<&T, PublicKey, u64> {
+        ProofMapIndex::new_in_family("auction.product_shares", product_id, &self.view)
+    }
+
+    /// Shares of the split product offered for sale by their holders.
+    pub fn share_listings(&self, product_id: &Hash) -> ProofMapIndex<&T, PublicKey, ShareListing> {
+        ProofMapIndex::new_in_family("auction.share_listings", product_id, &self.view)
+    }
+
+    /// History of ownership changes of the product.
+    pub fn product_history(&self, product_id: &Hash) -> ProofListIndex<&T, OwnershipRecord> {
+        ProofListIndex::new_in_family("auction.product_history", product_id, &self.view)
+    }
+
     /// Auctions.
     pub fn auctions(&self) -> ProofListIndex<&T, AuctionState> {
         ProofListIndex::new("auction.auctions", &self.view)
@@ -46,6 +101,26 @@ T: AsRef<dyn Snapshot>,
         ProofListIndex::new_in_family("auction.bids", &auction_id, &self.view)
     }
 
+    /// Deadline extensions of the auction.
+    pub fn auction_extensions(&self, auction_id: u64) -> ProofListIndex<&T, DeadlineExtension> {
+        ProofListIndex::new_in_family("auction.extensions", &auction_id, &self.view)
+    }
+
+    /// Sealed bid commitments of the auction.
+    pub fn auction_commitments(&self, auction_id: u64) -> ProofMapIndex<&T, PublicKey, BidCommitment> {
+        ProofMapIndex::new_in_family("auction.commitments", &auction_id, &self.view)
+    }
+
+    /// Proxy bids of the auction in order of placement.
+    pub fn auction_proxy_bids(&self, auction_id: u64) -> ProofListIndex<&T, ProxyBid> {
+        ProofListIndex::new_in_family("auction.proxy_bids", &auction_id, &self.view)
+    }
+
+    /// Asks of the reverse auction.
+    pub fn auction_asks(&self, auction_id: u64) -> ProofListIndex<&T, Ask> {
+        ProofListIndex::new_in_family("auction.asks", &auction_id, &self.view)
+    }
+
     /// Table for linking user and his property.
     pub fn user_products(&self, public_key: &PublicKey) -> ValueSetIndex<&T, Hash> {
         ValueSetIndex::new_in_family("auction.user_products", public_key, &self.view)
@@ -61,12 +136,88 @@ T: AsRef<dyn Snapshot>,
         MapIndex::new("auction.product_auction", &self.view)
     }
 
-    /// Method to get state hash. Depends on `users`, `products_states` and `auctions` tables.
+    /// Identifiers of auctions that are not closed yet.
+    pub fn open_auctions(&self) -> KeySetIndex<&T, u64> {
+        KeySetIndex::new("auction.open_auctions", &self.view)
+    }
+
+    /// Orders.
+    pub fn orders(&self) -> ProofListIndex<&T, Order> {
+        ProofListIndex::new("auction.orders", &self.view)
+    }
+
+    /// Resting buy orders of the product class by their order book keys.
+    pub fn order_book_bids(&self, class_id: &Hash) -> MapIndex<&T, Vec<u8>, u64> {
+        MapIndex::new_in_family("auction.order_book_bids", class_id, &self.view)
+    }
+
+    /// Resting sell orders of the product class by their order book keys.
+    pub fn order_book_asks(&self, class_id: &Hash) -> MapIndex<&T, Vec<u8>, u64> {
+        MapIndex::new_in_family("auction.order_book_asks", class_id, &self.view)
+    }
+
+    /// Trades of the product class.
+    pub fn class_trades(&self, class_id: &Hash) -> ProofListIndex<&T, Trade> {
+        ProofListIndex::new_in_family("auction.trades", class_id, &self.view)
+    }
+
+    /// Table for linking user and his orders.
+    pub fn user_orders(&self, public_key: &PublicKey) -> ListIndex<&T, u64> {
+        ListIndex::new_in_family("auction.user_orders", public_key, &self.view)
+    }
+
+    /// Table for linking product and its resting sell order.
+    pub fn product_order(&self) -> MapIndex<&T, Hash, u64> {
+        MapIndex::new("auction.product_order", &self.view)
+    }
+
+    /// Products listed for sale at a fixed price.
+    pub fn listings(&self) -> ProofMapIndex<&T, Hash, Listing> {
+        ProofMapIndex::new("auction.listings", &self.view)
+    }
+
+    /// Offers.
+    pub fn offers(&self) -> ProofListIndex<&T, Offer> {
+        ProofListIndex::new("auction.offers", &self.view)
+    }
+
+    /// Table for linking product and offers made for it.
+    pub fn product_offers(&self, product_id: &Hash) -> ListIndex<&T, u64> {
+        ListIndex::new_in_family("auction.product_offers", product_id, &self.view)
+    }
+
+    /// Table for linking user and offers made by him.
+    pub fn user_offers(&self, public_key: &PublicKey) -> ListIndex<&T, u64> {
+        ListIndex::new_in_family("auction.user_offers", public_key, &self.view)
+    }
+
+    /// Identifiers of offers that are still pending.
+    pub fn pending_offers(&self) -> KeySetIndex<&T, u64> {
+        KeySetIndex::new("auction.pending_offers", &self.view)
+    }
+
+    /// Funds transfers.
+    pub fn transfers(&self) -> ProofListIndex<&T, TransferRecord> {
+        ProofListIndex::new("auction.transfers", &self.view)
+    }
+
+    /// Table for linking user and transfers he sent or received.
+    pub fn user_transfers(&self, public_key: &PublicKey) -> ListIndex<&T, u64> {
+        ListIndex::new_in_family("auction.user_transfers", public_key, &self.view)
+    }
+
+    /// Method to get state hash. Depends on `users`, `products_states`, `auctions`, `orders`,
+    /// `listings`, `offers`, `transfers` and `categories` tables.
     pub fn state_hash(&self) -> Vec<Hash> {
         vec![
             self.users().merkle_root(),
             self.products_states().merkle_root(),
             self.auctions().merkle_root(),
+            self.orders().merkle_root(),
+            self.listings().merkle_root(),
+            self.offers().merkle_root(),
+            self.transfers().merkle_root(),
+            self.categories().merkle_root(),
         ]
     }
 }
@@ -78,7 +229,45 @@ impl<'a> AuctionSchema<&'a mut Fork> {
     }
 
     pub fn products_states_mut(&mut self) -> ProofMapIndex<&mut Fork, Hash, ProductState> {
-        ProofMapIndex::new("auction.producs_states", self.view)
+        ProofMapIndex::new("auction.products_states", self.view)
+    }
+
+    pub fn barcodes_mut(&mut self) -> MapIndex<&mut Fork, String, Hash> {
+        MapIndex::new("auction.barcodes", self.view)
+    }
+
+    pub fn barcode_sequence_mut(&mut self) -> Entry<&mut Fork, u64> {
+        Entry::new("auction.barcode_sequence", self.view)
+    }
+
+    pub fn categories_mut(&mut self) -> ProofMapIndex<&mut Fork, Hash, Category> {
+        ProofMapIndex::new("auction.categories", self.view)
+    }
+
+    pub fn category_products_mut(&mut self, category_id: &Hash) -> KeySetIndex<&mut Fork, Hash> {
+        KeySetIndex::new_in_family("auction.category_products", category_id, self.view)
+    }
+
+    pub fn category_auctions_mut(&mut self, category_id: &Hash) -> KeySetIndex<&mut Fork, u64> {
+        KeySetIndex::new_in_family("auction.category_auctions", category_id, self.view)
+    }
+
+    pub fn product_shares_mut(&mut self, product_id: &Hash) -> ProofMapIndex<&mut Fork, PublicKey, u64> {
+        ProofMapIndex::new_in_family("auction.product_shares", product_id, self.view)
+    }
+
+    pub fn share_listings_mut(
+        &mut self,
+        product_id: &Hash,
+    ) -> ProofMapIndex<&mut Fork, PublicKey, ShareListing> {
+        ProofMapIndex::new_in_family("auction.share_listings", product_id, self.view)
+    }
+
+    pub fn product_history_mut(
+        &mut self,
+        product_id: &Hash,
+    ) -> ProofListIndex<&mut Fork, OwnershipRecord> {
+        ProofListIndex::new_in_family("auction.product_history", product_id, self.view)
     }
 
     pub fn auctions_mut(&mut self) -> ProofListIndex<&mut Fork, AuctionState> {
@@ -86,7 +275,29 @@ impl<'a> AuctionSchema<&'a mut Fork> {
     }
 
     pub fn auction_bids_mut(&mut self, auction_id: u64) -> ProofListIndex<&mut Fork, Bid> {
-        ProofListIndex::new_in_family("aucriton.bids", &auction_id, self.view)
+        ProofListIndex::new_in_family("auction.bids", &auction_id, self.view)
+    }
+
+    pub fn auction_extensions_mut(
+        &mut self,
+        auction_id: u64,
+    ) -> ProofListIndex<&mut Fork, DeadlineExtension> {
+        ProofListIndex::new_in_family("auction.extensions", &auction_id, self.view)
+    }
+
+    pub fn auction_commitments_mut(
+        &mut self,
+        auction_id: u64,
+    ) -> ProofMapIndex<&mut Fork, PublicKey, BidCommitment> {
+        ProofMapIndex::new_in_family("auction.commitments", &auction_id, self.view)
+    }
+
+    pub fn auction_proxy_bids_mut(&mut self, auction_id: u64) -> ProofListIndex<&mut Fork, ProxyBid> {
+        ProofListIndex::new_in_family("auction.proxy_bids", &auction_id, self.view)
+    }
+
+    pub fn auction_asks_mut(&mut self, auction_id: u64) -> ProofListIndex<&mut Fork, Ask> {
+        ProofListIndex::new_in_family("auction.asks", &auction_id, self.view)
     }
 
     pub fn user_products_mut(&mut self, public_key: &PublicKey) -> ValueSetIndex<&mut Fork, Hash> {
@@ -100,6 +311,62 @@ impl<'a> AuctionSchema<&'a mut Fork> {
     pub fn product_auction_mut(&mut self) -> MapIndex<&mut Fork, Hash, u64> {
         MapIndex::new("auction.product_auction", self.view)
     }
+
+    pub fn open_auctions_mut(&mut self) -> KeySetIndex<&mut Fork, u64> {
+        KeySetIndex::new("auction.open_auctions", self.view)
+    }
+
+    pub fn orders_mut(&mut self) -> ProofListIndex<&mut Fork, Order> {
+        ProofListIndex::new("auction.orders", self.view)
+    }
+
+    pub fn order_book_bids_mut(&mut self, class_id: &Hash) -> MapIndex<&mut Fork, Vec<u8>, u64> {
+        MapIndex::new_in_family("auction.order_book_bids", class_id, self.view)
+    }
+
+    pub fn order_book_asks_mut(&mut self, class_id: &Hash) -> MapIndex<&mut Fork, Vec<u8>, u64> {
+        MapIndex::new_in_family("auction.order_book_asks", class_id, self.view)
+    }
+
+    pub fn class_trades_mut(&mut self, class_id: &Hash) -> ProofListIndex<&mut Fork, Trade> {
+        ProofListIndex::new_in_family("auction.trades", class_id, self.view)
+    }
+
+    pub fn user_orders_mut(&mut self, public_key: &PublicKey) -> ListIndex<&mut Fork, u64> {
+        ListIndex::new_in_family("auction.user_orders", public_key, self.view)
+    }
+
+    pub fn product_order_mut(&mut self) -> MapIndex<&mut Fork, Hash, u64> {
+        MapIndex::new("auction.product_order", self.view)
+    }
+
+    pub fn listings_mut(&mut self) -> ProofMapIndex<&mut Fork, Hash, Listing> {
+        ProofMapIndex::new("auction.listings", self.view)
+    }
+
+    pub fn offers_mut(&mut self) -> ProofListIndex<&mut Fork, Offer> {
+        ProofListIndex::new("auction.offers", self.view)
+    }
+
+    pub fn product_offers_mut(&mut self, product_id: &Hash) -> ListIndex<&mut Fork, u64> {
+        ListIndex::new_in_family("auction.product_offers", product_id, self.view)
+    }
+
+    pub fn user_offers_mut(&mut self, public_key: &PublicKey) -> ListIndex<&mut Fork, u64> {
+        ListIndex::new_in_family("auction.user_offers", public_key, self.view)
+    }
+
+    pub fn pending_offers_mut(&mut self) -> KeySetIndex<&mut Fork, u64> {
+        KeySetIndex::new("auction.pending_offers", self.view)
+    }
+
+    pub fn transfers_mut(&mut self) -> ProofListIndex<&mut Fork, TransferRecord> {
+        ProofListIndex::new("auction.transfers", self.view)
+    }
+
+    pub fn user_transfers_mut(&mut self, public_key: &PublicKey) -> ListIndex<&mut Fork, u64> {
+        ListIndex::new_in_family("auction.user_transfers", public_key, self.view)
+    }
 }
 
 ////=++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
@@ -108,25 +375,254 @@ impl<T> AuctionSchema<T>
 where
 T: AsRef<dyn Snapshot>,
 {
-    /// Method to generate product with unique barcode
-    pub fn make_product_with_uniq_barcode(&self, name: &str) -> Product {
-        let mut barcode: u64 = 0;
-        loop {
-            let new_product = Product::new(name, barcode);
-            if self.products_states().get(&new_product.hash()).is_none() {
-                break new_product;
-            }
-            barcode += 1;
-        }
+    /// Active proxy bids of the auction with their indices.
+    pub fn active_proxy_bids(&self, auction_id: u64) -> Vec<(u64, ProxyBid)> {
+        self.auction_proxy_bids(auction_id)
+            .iter()
+            .enumerate()
+            .filter(|(_, proxy)| proxy.active())
+            .map(|(index, proxy)| (index as u64, proxy))
+            .collect()
+    }
+
+    /// Active proxy bid of the user with its index.
+    pub fn active_proxy_bid(&self, auction_id: u64, user_id: &PublicKey) -> Option<(u64, ProxyBid)> {
+        self.active_proxy_bids(auction_id)
+            .into_iter()
+            .find(|(_, proxy)| proxy.public_key() == user_id)
+    }
+
+    /// Height of the latest committed block.
+    pub fn height(&self) -> u64 {
+        Schema::new(self.view.as_ref()).height().0
+    }
+
+    /// Checks if the product is auctioned, offered by a sell order or listed for sale.
+    pub fn product_locked(&self, product_id: &Hash) -> bool {
+        self.product_auction().contains(product_id)
+            || self.product_order().contains(product_id)
+            || self.listings().contains(product_id)
+    }
+
+    /// Categories of the auctioned products.
+    pub fn auction_categories(&self, auction_state: &AuctionState) -> BTreeSet<Hash> {
+        auction_state
+            .auction()
+            .product_ids()
+            .iter()
+            .filter_map(|product_id| self.products_states().get(product_id))
+            .map(|state| state.product().category().to_string())
+            .filter(|category| !category.is_empty())
+            .map(|category| category_id(&category))
+            .collect()
+    }
+
+    /// Resting orders of the product class on the given side in order of priority.
+    pub fn order_book(&self, class_id: &Hash, side: OrderSide) -> Vec<Order> {
+        let book = match side {
+            OrderSide::Buy => self.order_book_bids(class_id),
+            OrderSide::Sell => self.order_book_asks(class_id),
+        };
+        let orders = self.orders();
+        book.values().map(|order_id| orders.get(order_id).unwrap()).collect()
+    }
+
+    /// Resting order of the product class with the highest priority on the given side, orders
+    /// of the trader are skipped.
+    pub fn best_order(&self, class_id: &Hash, side: OrderSide, trader: &PublicKey) -> Option<Order> {
+        let book = match side {
+            OrderSide::Buy => self.order_book_bids(class_id),
+            OrderSide::Sell => self.order_book_asks(class_id),
+        };
+        let orders = self.orders();
+        book.values()
+            .map(|order_id| orders.get(order_id).unwrap())
+            .find(|order| order.public_key() != trader)
     }
 }
 
 /// Mutating helper methods.
 impl<'a> AuctionSchema<&'a mut Fork> {
 
+    /// Helper method to generate unique barcode. Caller-supplied barcodes can't have
+    /// the prefix of generated ones.
+    pub fn generate_barcode(&mut self) -> String {
+        let sequence = self.barcode_sequence().get().unwrap_or(0);
+        self.barcode_sequence_mut().set(sequence + 1);
+        format!("{}{}", AUTO_BARCODE_PREFIX, sequence)
+    }
+
     /// Helper method to make user owner of product
-    pub fn make_user_owner(&mut self, owner_key: &PublicKey, product: Product) {
-        self.user_products_mut(owner_key).insert(product.hash());
+    pub fn make_user_owner(&mut self, owner_key: &PublicKey, product: Product, royalty_percent: u64) {
+        let product_id = product.hash();
+        self.barcodes_mut().put(&product.barcode().to_string(), product_id);
+        if !product.category().is_empty() {
+            self.category_products_mut(&category_id(product.category())).insert(product_id);
+        }
+        self.user_products_mut(owner_key).insert(product_id);
+        self.products_states_mut().put(
+            &product_id,
+            ProductState::new(product, owner_key, false, &Hash::zero(), 0, owner_key, royalty_percent),
+            );
+        self.record_ownership(&product_id, OwnershipChange::Creation, owner_key, 0, 0);
+    }
+
+    /// Helper method to record the ownership change in the product history. Product state
+    /// gets the new owner and history root, retirement sets the retired flag.
+    fn record_ownership(
+        &mut self,
+        product_id: &Hash,
+        change: OwnershipChange,
+        new_owner: &PublicKey,
+        auction_id: u64,
+        price: u64,
+    ) {
+        let product_state = self.products_states().get(product_id).unwrap();
+        let record = OwnershipRecord::new(
+            change as u8,
+            product_state.owner(),
+            new_owner,
+            auction_id,
+            price,
+            0,
+            self.height(),
+            );
+        let retired = product_state.retired() || change == OwnershipChange::Retirement;
+        self.push_history(&product_state, record, new_owner, retired);
+    }
+
+    /// Helper method to push the record to the product history. Product state gets the given
+    /// owner and retired flag and the new history root.
+    fn push_history(
+        &mut self,
+        product_state: &ProductState,
+        record: OwnershipRecord,
+        owner: &PublicKey,
+        retired: bool,
+    ) {
+        let product_id = product_state.product().hash();
+        self.product_history_mut(&product_id).push(record);
+
+        let history_merkle_root = self.product_history(&product_id).merkle_root();
+        self.products_states_mut().put(
+            &product_id,
+            ProductState::new(
+                product_state.product(),
+                owner,
+                retired,
+                &history_merkle_root,
+                product_state.shares(),
+                product_state.creator(),
+                product_state.royalty_percent(),
+                ),
+                );
+    }
+
+    /// Helper method to split the product into shares held by its owner.
+    pub fn split_product(&mut self, product_id: &Hash, shares: u64) {
+        let product_state = self.products_states().get(product_id).unwrap();
+        self.product_shares_mut(product_id).put(product_state.owner(), shares);
+        self.set_product_shares(&product_state, shares);
+    }
+
+    /// Helper method to remove all shares of the product. Product is not split anymore.
+    pub fn clear_shares(&mut self, product_id: &Hash) {
+        let product_state = self.products_states().get(product_id).unwrap();
+        self.product_shares_mut(product_id).clear();
+        self.share_listings_mut(product_id).clear();
+        self.set_product_shares(&product_state, 0);
+    }
+
+    fn set_product_shares(&mut self, product_state: &ProductState, shares: u64) {
+        self.products_states_mut().put(
+            &product_state.product().hash(),
+            ProductState::new(
+                product_state.product(),
+                product_state.owner(),
+                product_state.retired(),
+                product_state.history_merkle_root(),
+                shares,
+                product_state.creator(),
+                product_state.royalty_percent(),
+                ),
+                );
+    }
+
+    /// Helper method to pass shares of the product to another holder. The change is recorded
+    /// in the product history with the total price, the owner of the product is kept.
+    pub fn move_shares(
+        &mut self,
+        product_id: &Hash,
+        from: &PublicKey,
+        to: &PublicKey,
+        amount: u64,
+        change: OwnershipChange,
+        price: u64,
+    ) {
+        let product_state = self.products_states().get(product_id).unwrap();
+        let record = OwnershipRecord::new(change as u8, from, to, 0, price, amount, self.height());
+        self.push_history(&product_state, record, product_state.owner(), product_state.retired());
+
+        let from_shares = self.product_shares(product_id).get(from).unwrap_or(0);
+        let to_shares = self.product_shares(product_id).get(to).unwrap_or(0);
+        if from_shares == amount {
+            self.product_shares_mut(product_id).remove(from);
+        } else {
+            self.product_shares_mut(product_id).put(from, from_shares - amount);
+        }
+        self.product_shares_mut(product_id).put(to, to_shares + amount);
+    }
+
+    /// Helper method to pay the royalty of the product sold at the given price to its creator.
+    /// Returns the paid royalty.
+    pub fn pay_royalty(&mut self, product_id: &Hash, price: u64) -> u64 {
+        let product_state = self.products_states().get(product_id).unwrap();
+        let percent = u128::from(product_state.royalty_percent());
+        let royalty = (u128::from(price) * percent / 100) as u64;
+        if royalty > 0 {
+            self.increase_user_balance(product_state.creator(), royalty);
+        }
+        royalty
+    }
+
+    /// Helper method to pay the auction proceeds. Creators of the products receive royalties,
+    /// the price of the bundle is divided among its products equally. The rest of the proceeds
+    /// of the split product is distributed among shareholders pro-rata, rounding remainder goes
+    /// to the seller. Shares are removed as the whole product passes to the winner.
+    pub fn pay_proceeds(&mut self, auction_state: &AuctionState, price: u64) {
+        let auction = auction_state.auction();
+        let product_ids = auction.product_ids();
+
+        let product_price = price / product_ids.len() as u64;
+        let mut royalties = 0;
+        for product_id in &product_ids {
+            royalties += self.pay_royalty(product_id, product_price);
+        }
+        let price = price - royalties;
+
+        let split = match product_ids.first() {
+            Some(product_id) if product_ids.len() == 1 => self
+                .products_states()
+                .get(product_id)
+                .map_or(false, |state| state.shares() > 0),
+            _ => false,
+        };
+        if !split {
+            self.increase_user_balance(auction.public_key(), price);
+            return;
+        }
+
+        let product_id = product_ids[0];
+        let total = self.products_states().get(&product_id).unwrap().shares();
+        let holdings: Vec<(PublicKey, u64)> = self.product_shares(&product_id).iter().collect();
+        let mut paid = 0;
+        for (holder, amount) in holdings {
+            let proceeds = (u128::from(price) * u128::from(amount) / u128::from(total)) as u64;
+            self.increase_user_balance(&holder, proceeds);
+            paid += proceeds;
+        }
+        self.increase_user_balance(auction.public_key(), price - paid);
+        self.clear_shares(&product_id);
     }
 
     /// Helper method to increase user balance.
@@ -198,5 +694,503 @@ impl<'a> AuctionSchema<&'a mut Fork> {
                 ),
                 );
     }
-}
 
+    /// Helper method to refresh the auction state after new bids. Bids made in the last
+    /// 'extension_window' blocks extend the auction, the extended bidding and revealing periods
+    /// must not overflow the height.
+    pub fn refresh_auction_state(
+        &mut self,
+        auction_state: &AuctionState,
+        bidder: &PublicKey,
+    ) -> Result<AuctionState, Error> {
+        let auction = auction_state.auction();
+        let auction_id = auction_state.id();
+
+        // Extend the auction if the bid is made in its last blocks.
+        let height = self.height();
+        let mut ends_at = auction_state.ends_at();
+        if auction.extension() > 0 && ends_at - height <= auction.extension_window() {
+            ends_at = ends_at
+                .checked_add(auction.extension())
+                .filter(|ends_at| ends_at.checked_add(auction.reveal_duration()).is_some())
+                .ok_or_else(|| Error::InvalidAuctionParameters)?;
+            let extension = DeadlineExtension::new(bidder, height, ends_at);
+            self.auction_extensions_mut(auction_id).push(extension);
+        }
+
+        let bids_merkle_root = match auction.auction_kind() {
+            AuctionKind::Reverse => self.auction_asks(auction_id).merkle_root(),
+            _ => self.auction_bids(auction_id).merkle_root(),
+        };
+        let extensions_merkle_root = self.auction_extensions(auction_id).merkle_root();
+        let auction_state = AuctionState::new(
+            auction_id,
+            auction,
+            &bids_merkle_root,
+            auction_state.closed(),
+            ends_at,
+            &extensions_merkle_root,
+            auction_state.started_at(),
+            auction_state.reserve(),
+            auction_state.reserve_revealed(),
+            auction_state.cancelled(),
+            );
+        self.auctions_mut().set(auction_id, auction_state.clone());
+        Ok(auction_state)
+    }
+
+    /// Helper method to release funds of the outbid bidder. Bidder with an active proxy bid
+    /// keeps its maximum reserved.
+    pub fn release_outbid(&mut self, auction_id: u64, bid: &Bid) {
+        if self.active_proxy_bid(auction_id, bid.public_key()).is_none() {
+            self.release_user_balance(bid.public_key(), bid.value());
+        }
+    }
+
+    /// Helper method to deactivate the proxy bid. Reserved maximum is released except 'keep' value.
+    pub fn deactivate_proxy_bid(&mut self, auction_id: u64, index: u64, keep: u64) {
+        let proxy = self.auction_proxy_bids(auction_id).get(index).unwrap();
+        self.release_user_balance(proxy.public_key(), proxy.max_value() - keep);
+        self.auction_proxy_bids_mut(auction_id).set(
+            index,
+            ProxyBid::new(proxy.public_key(), proxy.max_value(), false),
+            );
+    }
+
+    /// Helper method to deactivate all proxy bids of the auction. The winner keeps reserved
+    /// the value of its bid.
+    pub fn close_proxy_bids(&mut self, auction_id: u64, winner_bid: Option<&Bid>) {
+        for (index, proxy) in self.active_proxy_bids(auction_id) {
+            let keep = match winner_bid {
+                Some(bid) if bid.public_key() == proxy.public_key() => bid.value(),
+                _ => 0,
+            };
+            self.deactivate_proxy_bid(auction_id, index, keep);
+        }
+    }
+
+    /// Helper method to make bids on behalf of proxy bidders. The strongest competing proxy bid
+    /// (the earliest placed one wins a tie) outbids the highest bid by the minimum increment,
+    /// until no proxy bid can beat the highest bid. Exhausted proxy bids are deactivated.
+    pub fn resolve_proxy_bids(&mut self, auction_state: &AuctionState) {
+        let auction = auction_state.auction();
+        let auction_id = auction_state.id();
+
+        loop {
+            let top = self.auction_bids(auction_id).last();
+            let min_bid = auction.min_bid(top.as_ref().map(|b| b.value()));
+            let proxies = self.active_proxy_bids(auction_id);
+
+            let is_top = |proxy: &ProxyBid| top.as_ref().map_or(false, |b| b.public_key() == proxy.public_key());
+            let defender = proxies.iter().find(|(_, proxy)| is_top(proxy)).cloned();
+            let challenger = proxies
+                .iter()
+                .filter(|(_, proxy)| !is_top(proxy))
+                .fold(None, |best: Option<&(u64, ProxyBid)>, item| {
+                    if best.map_or(true, |b| item.1.max_value() > b.1.max_value()) {
+                        Some(item)
+                    } else {
+                        best
+                    }
+                })
+                .cloned();
+
+            let (challenger_index, challenger) = match challenger {
+                Some((index, ref proxy)) if proxy.max_value() >= min_bid => (index, proxy.clone()),
+                _ => break,
+            };
+
+            match (top, defender) {
+                (Some(top), Some((defender_index, defender))) => {
+                    let challenger_wins = challenger.max_value() > defender.max_value()
+                        || (challenger.max_value() == defender.max_value()
+                            && challenger_index < defender_index);
+                    if challenger_wins {
+                        // Defender bids its maximum and gets outbid.
+                        if defender.max_value() > top.value() {
+                            let bid = Bid::new(defender.public_key(), defender.max_value(), 1);
+                            self.auction_bids_mut(auction_id).push(bid);
+                        }
+                        self.deactivate_proxy_bid(auction_id, defender_index, 0);
+
+                        let value = challenger
+                            .max_value()
+                            .min(auction.min_next_bid(defender.max_value()));
+                        let bid = Bid::new(challenger.public_key(), value, 1);
+                        self.auction_bids_mut(auction_id).push(bid);
+                    } else {
+                        // Challenger bids its maximum and gets outbid.
+                        let bid = Bid::new(challenger.public_key(), challenger.max_value(), 1);
+                        self.auction_bids_mut(auction_id).push(bid);
+                        self.deactivate_proxy_bid(auction_id, challenger_index, 0);
+
+                        let value = defender
+                            .max_value()
+                            .min(auction.min_next_bid(challenger.max_value()));
+                        let bid = Bid::new(defender.public_key(), value, 1);
+                        self.auction_bids_mut(auction_id).push(bid);
+                    }
+                }
+                (top, _) => {
+                    // The highest bid is not backed by a proxy bid.
+                    if let Some(top) = top {
+                        self.release_user_balance(top.public_key(), top.value());
+                    }
+                    let bid = Bid::new(challenger.public_key(), min_bid, 1);
+                    self.auction_bids_mut(auction_id).push(bid);
+                }
+            }
+        }
+
+        // Deactivate proxy bids which can't beat the highest bid anymore.
+        if let Some(top) = self.auction_bids(auction_id).last() {
+            let min_bid = auction.min_next_bid(top.value());
+            for (index, proxy) in self.active_proxy_bids(auction_id) {
+                if proxy.public_key() != top.public_key() && proxy.max_value() < min_bid {
+                    self.deactivate_proxy_bid(auction_id, index, 0);
+                }
+            }
+        }
+    }
+
+    /// Helper method to settle sealed bid commitments. Returns the winner, its bid value and
+    /// the price to pay.
+    /// The highest revealed bid wins, the earliest revealed one wins a tie. Deposits of bidders
+    /// who did not reveal their bids are passed to the seller, funds of other losing bidders
+    /// are released.
+    fn settle_commitments(
+        &mut self,
+        auction_state: &AuctionState,
+    ) -> Option<(PublicKey, u64, u64)> {
+        let auction = auction_state.auction();
+
+        // Find the highest and the second highest revealed bids.
+        let mut winner_bid: Option<Bid> = None;
+        let mut second_value = auction.start_price();
+        for bid in self.auction_bids(auction_state.id()).iter() {
+            if winner_bid.as_ref().map_or(true, |b| bid.value() > b.value()) {
+                if let Some(prev) = winner_bid.take() {
+                    second_value = second_value.max(prev.value());
+                }
+                winner_bid = Some(bid);
+            } else {
+                second_value = second_value.max(bid.value());
+            }
+        }
+
+        let winner = winner_bid.map(|bid| {
+            let price = match auction.settlement_rule() {
+                Settlement::FirstPrice | Settlement::Uniform => bid.value(),
+                Settlement::SecondPrice => second_value,
+            };
+            // The winner pays at least the reserve price it has met.
+            let price = if auction.has_reserve() && auction_state.reserve_met(bid.value()) {
+                price.max(auction_state.reserve())
+            } else {
+                price
+            };
+            (*bid.public_key(), bid.value(), price)
+        });
+
+        let commitments: Vec<BidCommitment> = self.auction_commitments(auction_state.id())
+            .values()
+            .collect();
+        for commitment in commitments {
+            if !commitment.revealed() {
+                self.confirm_user_bid(commitment.public_key(), commitment.deposit());
+                self.increase_user_balance(auction.public_key(), commitment.deposit());
+                continue;
+            }
+
+            // Revealed bid has reserved its value, but not less than the deposit.
+            let reserved = commitment.value().max(commitment.deposit());
+            match winner {
+                // Winner keeps reserved the price to pay.
+                Some((ref key, _, price)) if key == commitment.public_key() => {
+                    self.release_user_balance(commitment.public_key(), reserved - price);
+                }
+                _ => self.release_user_balance(commitment.public_key(), reserved),
+            }
+        }
+
+        winner
+    }
+
+    /// Helper method to settle the multi-unit auction. Bids with the highest unit price win
+    /// (the earliest one wins a tie) until all units are allocated. Winners pay their unit price,
+    /// or the lowest winning unit price under uniform settlement. Funds of losing bids are released,
+    /// unsold units stay with the seller.
+    fn settle_multi_unit(&mut self, auction_state: &AuctionState) {
+        let auction = auction_state.auction();
+
+        // Stable sort keeps the earlier bid first among bids with the same unit price.
+        let mut bids: Vec<Bid> = self.auction_bids(auction_state.id()).iter().collect();
+        bids.sort_by(|a, b| b.value().cmp(&a.value()));
+
+        // Allocate units.
+        let mut remaining = auction.product_ids().len() as u64;
+        let allocations: Vec<(Bid, u64)> = bids
+            .into_iter()
+            .map(|bid| {
+                let units = bid.quantity().min(remaining);
+                remaining -= units;
+                (bid, units)
+            })
+            .collect();
+        let clearing_price = allocations
+            .iter()
+            .filter(|(_, units)| *units > 0)
+            .map(|(bid, _)| bid.value())
+            .min();
+
+        let mut units_for_sale = auction.product_ids().into_iter();
+        for (bid, units) in allocations {
+            let price = match (auction.settlement_rule(), clearing_price) {
+                (Settlement::Uniform, Some(clearing_price)) => clearing_price,
+                _ => bid.value(),
+            };
+            let reserved = bid.value() * bid.quantity();
+            let payment = price * units;
+
+            // Release the rest of reserved funds.
+            if reserved > payment {
+                self.release_user_balance(bid.public_key(), reserved - payment);
+            }
+            if units == 0 {
+                continue;
+            }
+
+            self.confirm_user_bid(bid.public_key(), payment);
+            let mut royalties = 0;
+            for product_id in units_for_sale.by_ref().take(units as usize) {
+                royalties += self.pay_royalty(&product_id, price);
+                self.transfer_product(
+                    &product_id,
+                    bid.public_key(),
+                    OwnershipChange::AuctionSale,
+                    auction_state.id(),
+                    price,
+                    );
+            }
+            self.increase_user_balance(auction.public_key(), payment - royalties);
+        }
+    }
+
+    /// Helper method to settle the reverse auction. The lowest ask wins, the buyer pays its value
+    /// out of the reserved budget and receives the offered product. The rest of the budget is released.
+    fn settle_reverse(&mut self, auction_state: &AuctionState) {
+        let auction = auction_state.auction();
+        let buyer = auction.public_key();
+        let budget = auction.start_price();
+
+        match self.auction_asks(auction_state.id()).last() {
+            Some(ask) => {
+                self.release_user_balance(buyer, budget - ask.value());
+                self.confirm_user_bid(buyer, ask.value());
+                let royalty = self.pay_royalty(ask.product_id(), ask.value());
+                self.increase_user_balance(ask.public_key(), ask.value() - royalty);
+
+                self.product_auction_mut().remove(ask.product_id());
+                self.transfer_product(
+                    ask.product_id(),
+                    buyer,
+                    OwnershipChange::AuctionSale,
+                    auction_state.id(),
+                    ask.value(),
+                    );
+            }
+            None => self.release_user_balance(buyer, budget),
+        }
+    }
+
+    /// Helper method to put the order to the order book.
+    pub fn rest_order(&mut self, order: &Order) {
+        let class = class_id(order.class());
+        match order.order_side() {
+            OrderSide::Buy => self.order_book_bids_mut(&class).put(&order.book_key(), order.id()),
+            OrderSide::Sell => self.order_book_asks_mut(&class).put(&order.book_key(), order.id()),
+        }
+    }
+
+    /// Helper method to close the order. Resting order is removed from the order book.
+    pub fn close_order(&mut self, order: &Order, status: OrderStatus) {
+        let class = class_id(order.class());
+        match order.order_side() {
+            OrderSide::Buy => self.order_book_bids_mut(&class).remove(&order.book_key()),
+            OrderSide::Sell => {
+                self.order_book_asks_mut(&class).remove(&order.book_key());
+                self.product_order_mut().remove(order.product_id());
+            }
+        }
+        self.orders_mut().set(order.id(), order.with_status(status));
+    }
+
+    /// Helper method to match buy and sell orders. The buyer pays the price out of its reserved
+    /// funds, the rest of them is released. The seller receives the price and passes the product
+    /// to the buyer.
+    pub fn execute_trade(&mut self, buy_order: &Order, sell_order: &Order, price: u64) {
+        let buyer = buy_order.public_key();
+        self.confirm_user_bid(buyer, price);
+        if buy_order.price() > price {
+            self.release_user_balance(buyer, buy_order.price() - price);
+        }
+        self.increase_user_balance(sell_order.public_key(), price);
+
+        self.close_order(buy_order, OrderStatus::Filled);
+        self.close_order(sell_order, OrderStatus::Filled);
+        self.transfer_product(sell_order.product_id(), buyer, OwnershipChange::Trade, 0, price);
+
+        let height = self.height();
+        let trade = Trade::new(buy_order.id(), sell_order.id(), sell_order.product_id(), price, height);
+        self.class_trades_mut(&class_id(sell_order.class())).push(trade);
+    }
+
+    /// Helper method to close the pending offer. Funds reserved by the offer are released.
+    pub fn close_offer(&mut self, offer: &Offer, status: OfferStatus) {
+        self.release_user_balance(offer.public_key(), offer.value());
+        self.pending_offers_mut().remove(&offer.id());
+        self.offers_mut().set(offer.id(), offer.with_status(status));
+    }
+
+    /// Helper method to open the auction. It is added to the categories of its products.
+    pub fn open_auction(&mut self, auction_state: &AuctionState) {
+        for category in self.auction_categories(auction_state) {
+            self.category_auctions_mut(&category).insert(auction_state.id());
+        }
+        self.open_auctions_mut().insert(auction_state.id());
+    }
+
+    /// Helper method to remove the closed auction from open auctions.
+    fn remove_open_auction(&mut self, auction_state: &AuctionState) {
+        for category in self.auction_categories(auction_state) {
+            self.category_auctions_mut(&category).remove(&auction_state.id());
+        }
+        self.open_auctions_mut().remove(&auction_state.id());
+    }
+
+    /// Helper method to pass the product to the new owner. The change is recorded in the product
+    /// history with the auction id and the price.
+    pub fn transfer_product(
+        &mut self,
+        product_id: &Hash,
+        new_owner: &PublicKey,
+        change: OwnershipChange,
+        auction_id: u64,
+        price: u64,
+    ) {
+        let product_state = self.products_states().get(product_id).unwrap();
+
+        // Remove product from the previous owner.
+        self.user_products_mut(product_state.owner()).remove(product_id);
+
+        // Pass it to the new owner.
+        self.user_products_mut(new_owner).insert(*product_id);
+
+        // Change product owner.
+        self.record_ownership(product_id, change, new_owner, auction_id, price);
+    }
+
+    /// Helper method to retire the product. Product state and history are kept for audit.
+    pub fn retire_product(&mut self, product_id: &Hash) {
+        let product_state = self.products_states().get(product_id).unwrap();
+        self.user_products_mut(product_state.owner()).remove(product_id);
+        let category = category_id(product_state.product().category());
+        self.category_products_mut(&category).remove(product_id);
+        self.record_ownership(product_id, OwnershipChange::Retirement, product_state.owner(), 0, 0);
+    }
+
+    /// Helper method to settle the auction. The last bid wins and pays its value. In sealed
+    /// auctions the highest revealed bid wins and pays according to the settlement rule.
+    /// The winner receives all auctioned products if its bid meets the reserve price, auction gets
+    /// closed.
+    /// Multi-unit auctions distribute units among the highest bids, reverse auctions are won by
+    /// the lowest ask.
+    pub fn settle_auction(&mut self, auction_state: &AuctionState) {
+        let auction = auction_state.auction();
+
+        let winner = match auction.auction_kind() {
+            AuctionKind::English | AuctionKind::Dutch => {
+                let last_bid = self.auction_bids(auction_state.id()).last();
+                self.close_proxy_bids(auction_state.id(), last_bid.as_ref());
+                last_bid.map(|bid| (*bid.public_key(), bid.value(), bid.value()))
+            }
+            AuctionKind::Sealed => self.settle_commitments(auction_state),
+            AuctionKind::MultiUnit => {
+                self.settle_multi_unit(auction_state);
+                None
+            }
+            AuctionKind::Reverse => {
+                self.settle_reverse(auction_state);
+                None
+            }
+        };
+
+        // Product stays with the seller if the reserve price is not met. The seller who has not
+        // revealed the reserve price backs out of the auction and pays the cancellation penalty.
+        let winner = match winner {
+            Some((winner_key, value, price)) if !auction_state.reserve_met(value) => {
+                self.release_user_balance(&winner_key, price);
+                if !auction_state.reserve_revealed() {
+                    self.pay_cancel_penalty(auction.public_key(), &winner_key, value);
+                }
+                None
+            }
+            winner => winner,
+        };
+
+        if let Some((winner_key, _, price)) = winner {
+            // Decrease winner balance.
+            let winner = self.users().get(&winner_key).unwrap();
+            self.confirm_user_bid(winner.pub_key(), price);
+
+            // Increase seller balance, shareholders of the split product are paid pro-rata.
+            self.pay_proceeds(auction_state, price);
+
+            // Pass the product to the winner.
+            for product_id in auction.product_ids() {
+                self.transfer_product(
+                    &product_id,
+                    winner.pub_key(),
+                    OwnershipChange::AuctionSale,
+                    auction_state.id(),
+                    price,
+                    );
+            }
+        };
+
+        for product_id in auction.product_ids() {
+            self.product_auction_mut().remove(&product_id);
+        }
+        self.remove_open_auction(auction_state);
+        // Close auction
+        self.auctions_mut().set(auction_state.id(), auction_state.close());
+    }
+
+    /// Helper method to pay the cancellation penalty for the highest bid with the given value
+    /// to its bidder. The penalty is limited by the seller balance.
+    fn pay_cancel_penalty(&mut self, seller: &PublicKey, bidder: &PublicKey, value: u64) {
+        let balance = self.users().get(seller).unwrap().balance();
+        let penalty = cancel_penalty(value).min(balance);
+        if penalty > 0 {
+            self.decrease_user_balance(seller, penalty);
+            self.increase_user_balance(bidder, penalty);
+        }
+    }
+
+    /// Helper method to cancel the auction. Auction gets closed without a winner. Budget of
+    /// the reverse auction is released to the buyer.
+    pub fn cancel_auction(&mut self, auction_state: &AuctionState) {
+        let auction = auction_state.auction();
+        for product_id in auction.product_ids() {
+            self.product_auction_mut().remove(&product_id);
+        }
+        if auction.auction_kind() == AuctionKind::Reverse {
+            if let Some(ask) = self.auction_asks(auction_state.id()).last() {
+                self.product_auction_mut().remove(ask.product_id());
+            }
+            self.release_user_balance(auction.public_key(), auction.start_price());
+        }
+        self.remove_open_auction(auction_state);
+        self.auctions_mut().set(auction_state.id(), auction_state.cancel());
+    }
+}
@@ -0,0 +1,15 @@
+//! Product share structures definition.
+
+use exonum::crypto::PublicKey;
+
+encoding_struct! {
+    /// Shares of the split product offered for sale by the holder.
+    struct ShareListing {
+        /// Shareholder.
+        public_key: &PublicKey,
+        /// Number of shares for sale.
+        amount: u64,
+        /// Price of one share.
+        price: u64,
+    }
+}
@@ -4,21 +4,33 @@
 // ECR-1771 for the details.
 #![allow(bare_trait_objects)]
 
+use std::collections::BTreeSet;
+
 use exonum::{
     blockchain::{ExecutionResult, Transaction, Schema},
-    crypto::{PublicKey, Hash},
+    crypto::{CryptoHash, PublicKey, Hash},
     messages::Message, storage::Fork,
     storage::Snapshot,
 };
 
 use schema::AuctionSchema;
-use user::User;
-use auction::{Auction, AuctionState, Bid};
-use product::ProductState;
+use product::{OwnershipChange, Product, ProductAttribute, ProductCondition};
+use user::{TransferRecord, User};
+use auction::{
+    cancel_penalty, Ask, Auction, AuctionKind, AuctionState, Bid, BidCommitment, IncrementTier,
+    ProxyBid, SealedBid, SealedReserve, Settlement,
+};
+use category::{category_id, Category};
+use listing::Listing;
+use share::ShareListing;
+use offer::{Offer, OfferStatus};
+use order::{class_id, Order, OrderSide, OrderStatus};
 use error::Error;
 
 use AUCTION_SERVICE_ID;
+use AUTO_BARCODE_PREFIX;
 use INITIAL_BALANCE;
+use MAX_ROYALTY_PERCENT;
 
 
 transactions! {
@@ -39,6 +51,20 @@ transactions! {
             public_key: &PublicKey,
             /// Product name.
             name: &str,
+            /// Product barcode, GTIN or serial number. Generated if empty.
+            barcode: &str,
+            /// Product description.
+            description: &str,
+            /// Product category.
+            category: &str,
+            /// Product condition: 0 - new, 1 - used, 2 - refurbished, 3 - for parts.
+            condition: u8,
+            /// Hash of the off-chain image or document, zero hash if there is none.
+            content_hash: &Hash,
+            /// Arbitrary attributes with distinct keys.
+            attributes: Vec<ProductAttribute>,
+            /// Royalty paid to the creator on auction resale, in percent of the price.
+            royalty_percent: u64,
         }
 
         /// Transaction to issue funds.
@@ -49,12 +75,41 @@ transactions! {
 
         /// Transaction type for adding a new item.
         struct CreateAuction {
-            /// Public key of the user selling the product.
+            /// Public key of the user selling the product. Buyer in reverse auctions.
             public_key: &PublicKey,
-            /// Product with 'product_id' is auctioned.
-            product_id: &Hash,
-            /// Start price.
+            /// Products with 'product_ids' are auctioned. Units of a multi-unit auction, otherwise
+            /// the bundle passed to the winner at once. Empty in reverse auctions.
+            product_ids: Vec<Hash>,
+            /// Start price. Maximum budget reserved by the buyer in reverse auctions.
             start_price: u64,
+            /// Auction duration in blocks.
+            duration: u64,
+            /// Bids made in the last 'extension_window' blocks extend the auction.
+            extension_window: u64,
+            /// Number of blocks the auction is extended by.
+            extension: u64,
+            /// Auction kind: 0 - English, 1 - Dutch, 2 - sealed, 3 - multi-unit, 4 - reverse.
+            kind: u8,
+            /// Dutch auction: price drop per block.
+            price_step: u64,
+            /// Dutch auction: price can't drop below this value.
+            floor_price: u64,
+            /// Sealed auction: number of blocks after bidding ends during which bids are revealed.
+            reveal_duration: u64,
+            /// Sealed auction: deposit reserved with each bid commitment.
+            deposit: u64,
+            /// Settlement rule: 0 - first price, 1 - second price (sealed auctions only).
+            settlement: u8,
+            /// Hash of the `SealedReserve`, zero hash if the auction has no reserve price.
+            reserve_hash: &Hash,
+            /// Price ending the auction instantly, zero if not available (English auctions only).
+            buy_now_price: u64,
+            /// Minimum bid increment rule: 0 - any, 1 - absolute, 2 - percent, 3 - tiered.
+            increment_rule: u8,
+            /// Absolute or percentage increment.
+            increment: u64,
+            /// Tiered increments sorted by price band.
+            increment_tiers: Vec<IncrementTier>,
         }
 
         struct MakeBid {
@@ -62,8 +117,10 @@ transactions! {
             public_key: &PublicKey,
             /// Auction ID where a bid must be made.
             auction_id: u64,
-            /// Bid value.
+            /// Bid value. Unit price in multi-unit auctions.
             value: u64,
+            /// Number of units, 1 for single product auctions.
+            quantity: u64,
         }
 
         /// Close auction.
@@ -73,6 +130,288 @@ transactions! {
             /// Key of the closing party.
             closing_party: &PublicKey,
         }
+
+        /// Commit sealed bid.
+        struct CommitBid {
+            /// Bidder.
+            public_key: &PublicKey,
+            /// Auction ID where a bid must be made.
+            auction_id: u64,
+            /// Hash of the `SealedBid`.
+            commitment: &Hash,
+        }
+
+        /// Reveal sealed bid.
+        struct RevealBid {
+            /// Bidder.
+            public_key: &PublicKey,
+            /// Auction ID where the bid was committed.
+            auction_id: u64,
+            /// Bid value.
+            value: u64,
+            /// Salt used in the commitment.
+            salt: &Hash,
+        }
+
+        /// Reveal reserve price of the auction.
+        struct RevealReserve {
+            /// Seller.
+            public_key: &PublicKey,
+            /// Auction ID.
+            auction_id: u64,
+            /// Reserve price.
+            value: u64,
+            /// Salt used in the commitment.
+            salt: &Hash,
+        }
+
+        /// Buy the product at the buy now price ending the auction.
+        struct BuyNow {
+            /// Buyer.
+            public_key: &PublicKey,
+            /// Auction ID.
+            auction_id: u64,
+        }
+
+        /// Cancel auction by the seller.
+        struct CancelAuction {
+            /// Seller.
+            public_key: &PublicKey,
+            /// Auction to cancel.
+            auction_id: u64,
+        }
+
+        /// Place proxy bid. The system bids on behalf of the user up to the maximum.
+        struct PlaceProxyBid {
+            /// Bidder.
+            public_key: &PublicKey,
+            /// Auction ID where bids must be made.
+            auction_id: u64,
+            /// Maximum value of the bids.
+            max_value: u64,
+        }
+
+        /// Offer a product in the reverse auction.
+        struct MakeAsk {
+            /// Seller.
+            public_key: &PublicKey,
+            /// Auction ID where the ask must be made.
+            auction_id: u64,
+            /// Offered product.
+            product_id: &Hash,
+            /// Asked price.
+            value: u64,
+        }
+
+        /// Place limit order to the order book of the product class.
+        struct PlaceOrder {
+            /// User placing the order.
+            public_key: &PublicKey,
+            /// Product class, products with the same name belong to the same class.
+            class: &str,
+            /// Order side: 0 - buy, 1 - sell.
+            side: u8,
+            /// Product offered by the sell order, zero hash for buy orders.
+            product_id: &Hash,
+            /// Limit price.
+            price: u64,
+        }
+
+        /// Cancel resting order.
+        struct CancelOrder {
+            /// User who placed the order.
+            public_key: &PublicKey,
+            /// Order to cancel.
+            order_id: u64,
+        }
+
+        /// List the product for sale at a fixed price.
+        struct ListForSale {
+            /// Seller.
+            public_key: &PublicKey,
+            /// Product to list.
+            product_id: &Hash,
+            /// Price of the product.
+            price: u64,
+        }
+
+        /// Remove the product from sale.
+        struct Delist {
+            /// Seller.
+            public_key: &PublicKey,
+            /// Listed product.
+            product_id: &Hash,
+        }
+
+        /// Buy the listed product.
+        struct Buy {
+            /// Buyer.
+            public_key: &PublicKey,
+            /// Listed product.
+            product_id: &Hash,
+            /// Price the buyer agrees to pay, must match the listing price.
+            price: u64,
+        }
+
+        /// Offer to buy the product from its owner.
+        struct MakeOffer {
+            /// User making the offer.
+            public_key: &PublicKey,
+            /// Product the offer is made for.
+            product_id: &Hash,
+            /// Offered price.
+            value: u64,
+            /// Height of the block from which the offer can't be accepted.
+            expires_at: u64,
+        }
+
+        /// Accept the offer selling the product.
+        struct AcceptOffer {
+            /// Product owner.
+            public_key: &PublicKey,
+            /// Offer to accept.
+            offer_id: u64,
+        }
+
+        /// Reject the offer.
+        struct RejectOffer {
+            /// Product owner.
+            public_key: &PublicKey,
+            /// Offer to reject.
+            offer_id: u64,
+        }
+
+        /// Withdraw the offer releasing its funds.
+        struct WithdrawOffer {
+            /// User who made the offer.
+            public_key: &PublicKey,
+            /// Offer to withdraw.
+            offer_id: u64,
+        }
+
+        /// Transfer funds to another user.
+        struct Transfer {
+            /// Sender.
+            public_key: &PublicKey,
+            /// Receiver.
+            to: &PublicKey,
+            /// Amount of funds.
+            amount: u64,
+            /// Auxiliary number to guarantee non-idempotence of transactions.
+            seed: u64,
+        }
+
+        /// Hand the product to another user.
+        struct TransferProduct {
+            /// Product owner.
+            public_key: &PublicKey,
+            /// Receiver.
+            to: &PublicKey,
+            /// Product to transfer.
+            product_id: &Hash,
+            /// Auxiliary number to guarantee non-idempotence of transactions.
+            seed: u64,
+        }
+
+        /// Retire the destroyed or redeemed product.
+        struct BurnProduct {
+            /// Product owner.
+            public_key: &PublicKey,
+            /// Product to retire.
+            product_id: &Hash,
+        }
+
+        /// Add category to the taxonomy.
+        struct AddCategory {
+            /// Service key of the validator node administrating the taxonomy.
+            admin: &PublicKey,
+            /// Category name.
+            name: &str,
+            /// Name of the parent category, empty for top level categories.
+            parent: &str,
+            /// Category description.
+            description: &str,
+            /// Auxiliary number to guarantee non-idempotence of transactions.
+            seed: u64,
+        }
+
+        /// Remove unused category from the taxonomy.
+        struct RemoveCategory {
+            /// Service key of the validator node administrating the taxonomy.
+            admin: &PublicKey,
+            /// Category name.
+            name: &str,
+            /// Auxiliary number to guarantee non-idempotence of transactions.
+            seed: u64,
+        }
+
+        /// Split the product into shares held by its owner.
+        struct SplitProduct {
+            /// Product owner.
+            public_key: &PublicKey,
+            /// Product to split.
+            product_id: &Hash,
+            /// Number of shares.
+            shares: u64,
+        }
+
+        /// Hand shares of the split product to another user.
+        struct TransferShares {
+            /// Shareholder.
+            public_key: &PublicKey,
+            /// Receiver.
+            to: &PublicKey,
+            /// Split product.
+            product_id: &Hash,
+            /// Number of shares.
+            amount: u64,
+            /// Auxiliary number to guarantee non-idempotence of transactions.
+            seed: u64,
+        }
+
+        /// Offer shares of the split product for sale, zero amount removes the offer.
+        struct ListShares {
+            /// Shareholder.
+            public_key: &PublicKey,
+            /// Split product.
+            product_id: &Hash,
+            /// Number of shares for sale.
+            amount: u64,
+            /// Price of one share.
+            price: u64,
+        }
+
+        /// Buy shares offered for sale.
+        struct BuyShares {
+            /// Buyer.
+            public_key: &PublicKey,
+            /// Split product.
+            product_id: &Hash,
+            /// Shareholder offering shares.
+            seller: &PublicKey,
+            /// Number of shares.
+            amount: u64,
+            /// Price of one share the buyer agrees to pay, must match the offered price.
+            price: u64,
+            /// Auxiliary number to guarantee non-idempotence of transactions.
+            seed: u64,
+        }
+
+        /// Join all shares of the product making their holder its owner.
+        struct MergeShares {
+            /// Holder of all shares.
+            public_key: &PublicKey,
+            /// Split product.
+            product_id: &Hash,
+        }
+
+        /// Expire the pending offer releasing its value.
+        struct ExpireOffer {
+            /// Offer to expire.
+            offer_id: u64,
+            /// Key of the closing party.
+            closing_party: &PublicKey,
+        }
     }
 }
 
@@ -105,9 +444,51 @@ impl Transaction for MakeProduct {
     fn execute(&self, fork: &mut Fork) -> ExecutionResult {
         let mut schema = AuctionSchema::new(fork);
 
-        let user = schema.users().get(self.public_key()).unwrap();
-        let product = schema.make_product_with_uniq_barcode(self.name());
-        schema.make_user_owner(user.pub_key(), product);
+        // Check if the user is registered.
+        let user = schema
+            .users()
+            .get(self.public_key())
+            .ok_or_else(|| Error::UserIsNotRegistered)?;
+
+        // Check product metadata. Attribute keys must be distinct.
+        let attributes = self.attributes();
+        let keys: BTreeSet<&str> = attributes.iter().map(|attribute| attribute.key()).collect();
+        if ProductCondition::from_u8(self.condition()).is_none()
+            || keys.len() != attributes.len()
+            || self.royalty_percent() > MAX_ROYALTY_PERCENT
+        {
+            Err(Error::InvalidProductParameters)?;
+        }
+
+        // Category must be in the taxonomy.
+        let category = category_id(self.category());
+        if !self.category().is_empty() && !schema.categories().contains(&category) {
+            Err(Error::CategoryNotFound)?;
+        }
+
+        // Barcode must be unique, the prefix of generated barcodes is reserved.
+        let barcode = if self.barcode().is_empty() {
+            schema.generate_barcode()
+        } else {
+            if self.barcode().starts_with(AUTO_BARCODE_PREFIX) {
+                Err(Error::InvalidProductParameters)?;
+            }
+            if schema.barcodes().contains(&self.barcode().to_string()) {
+                Err(Error::BarcodeAlreadyUsed)?;
+            }
+            self.barcode().to_string()
+        };
+
+        let product = Product::new(
+            self.name(),
+            &barcode,
+            self.description(),
+            self.category(),
+            self.condition(),
+            self.content_hash(),
+            self.attributes(),
+            );
+        schema.make_user_owner(user.pub_key(), product, self.royalty_percent());
 
         Ok(())
     }
@@ -137,39 +518,164 @@ impl Transaction for CreateAuction {
         let mut schema = AuctionSchema::new(fork);
         let auction = Auction::new(
             self.public_key(),
-            self.product_id(),
+            self.product_ids(),
             self.start_price(),
+            self.duration(),
+            self.extension_window(),
+            self.extension(),
+            self.kind(),
+            self.price_step(),
+            self.floor_price(),
+            self.reveal_duration(),
+            self.deposit(),
+            self.settlement(),
+            self.reserve_hash(),
+            self.buy_now_price(),
+            self.increment_rule(),
+            self.increment(),
+            self.increment_tiers(),
             );
 
+        // Check auction parameters. Auction must last at least one block.
+        let settlement = Settlement::from_u8(auction.settlement());
+        let valid = match AuctionKind::from_u8(auction.kind()) {
+            // Buy now closes the auction before the reserve price could be revealed.
+            Some(AuctionKind::English) => {
+                settlement == Some(Settlement::FirstPrice)
+                    && (!auction.has_reserve() || auction.reveal_duration() > 0)
+                    && (auction.buy_now_price() == 0
+                        || (auction.buy_now_price() > auction.start_price()
+                            && !auction.has_reserve()))
+            }
+            Some(AuctionKind::Dutch) => {
+                auction.price_step() > 0
+                    && auction.floor_price() < auction.start_price()
+                    && auction.extension() == 0
+                    && settlement == Some(Settlement::FirstPrice)
+                    && !auction.has_reserve()
+                    && auction.buy_now_price() == 0
+            }
+            Some(AuctionKind::Sealed) => {
+                auction.reveal_duration() > 0
+                    && auction.deposit() > 0
+                    && auction.extension() == 0
+                    && settlement != Some(Settlement::Uniform)
+                    && settlement.is_some()
+                    && auction.buy_now_price() == 0
+            }
+            Some(AuctionKind::MultiUnit) => {
+                (settlement == Some(Settlement::FirstPrice)
+                    || settlement == Some(Settlement::Uniform))
+                    && !auction.has_reserve()
+                    && auction.buy_now_price() == 0
+            }
+            Some(AuctionKind::Reverse) => {
+                auction.start_price() > 0
+                    && settlement == Some(Settlement::FirstPrice)
+                    && !auction.has_reserve()
+                    && auction.buy_now_price() == 0
+            }
+            None => false,
+        };
+        if auction.duration() == 0 || !auction.has_valid_increment() || !valid {
+            Err(Error::InvalidAuctionParameters)?;
+        }
+
+        // Auctioned products must be distinct. Products of the reverse auction are offered
+        // by sellers.
+        let product_ids = auction.product_ids();
+        let reverse = auction.auction_kind() == AuctionKind::Reverse;
+        let distinct: BTreeSet<Hash> = product_ids.iter().cloned().collect();
+        if product_ids.is_empty() != reverse || distinct.len() != product_ids.len() {
+            Err(Error::InvalidAuctionParameters)?;
+        }
+
         // Check if the user is registered.
         let user = schema
             .users()
             .get(auction.public_key())
             .ok_or_else(|| Error::UserIsNotRegistered)?;
 
-        // Check if product exists.
-        let product = schema
-            .products_states()
-            .get(auction.product_id())
-            .ok_or_else(|| Error::ProductNotFound)?;
+        // Check if the buyer has enough money for the budget.
+        if reverse && user.balance() < auction.start_price() {
+            Err(Error::InsufficientFunds)?;
+        }
 
-        // Check if the user owns the product.
-        if product.owner() != user.pub_key() {
-            Err(Error::ProductNotOwned)?;
+        let mut products = Vec::new();
+        for product_id in &product_ids {
+            // Check if product exists.
+            let product = schema
+                .products_states()
+                .get(product_id)
+                .ok_or_else(|| Error::ProductNotFound)?;
+
+            // Retired products can't be traded.
+            if product.retired() {
+                Err(Error::ProductRetired)?;
+            }
+
+            // Check if the user owns the product. Split product is auctioned alone by the holder
+            // of the majority of its shares.
+            if product.shares() > 0 {
+                let holding = schema.product_shares(product_id).get(user.pub_key()).unwrap_or(0);
+                if holding <= product.shares() - holding {
+                    Err(Error::ProductNotOwned)?;
+                }
+                if product_ids.len() != 1 || auction.auction_kind() == AuctionKind::MultiUnit {
+                    Err(Error::InvalidAuctionParameters)?;
+                }
+            } else if product.owner() != user.pub_key() {
+                Err(Error::ProductNotOwned)?;
+            }
+
+            // Check if the product isn't auctioned or offered already.
+            if schema.product_locked(product_id) {
+                Err(Error::ProductAlreadyAuctioned)?;
+            }
+
+            products.push(product.product());
         }
 
-        // Check if the product isn't auctioned already.
-        if schema.product_auction().get(auction.product_id()).is_some() {
-            Err(Error::ProductAlreadyAuctioned)?;
+        // Units of a multi-unit auction must be identical.
+        let multi_unit = auction.auction_kind() == AuctionKind::MultiUnit;
+        if multi_unit && products.iter().any(|product| product.name() != products[0].name()) {
+            Err(Error::InvalidAuctionParameters)?;
+        }
+
+        // Reserve the budget of the reverse auction.
+        if reverse {
+            schema.reserve_user_balance(user.pub_key(), auction.start_price());
         }
 
         // Create a new auction.
         let auction_id = schema.auctions().len();
-        let product_id = *auction.product_id();
-        let state = AuctionState::new(auction_id, auction, &Hash::zero(), false);
+        let started_at = schema.height();
+        let ends_at = started_at
+            .checked_add(auction.duration())
+            .ok_or_else(|| Error::InvalidAuctionParameters)?;
+
+        // The revealing period must end at a representable height.
+        if ends_at.checked_add(auction.reveal_duration()).is_none() {
+            Err(Error::InvalidAuctionParameters)?;
+        }
+        let state = AuctionState::new(
+            auction_id,
+            auction,
+            &Hash::zero(),
+            false,
+            ends_at,
+            &Hash::zero(),
+            started_at,
+            0,
+            false,
+            false,
+            );
 
-        schema.auctions_mut().push(state);
-        schema.product_auction_mut().put(&product_id, auction_id);
+        schema.auctions_mut().push(state.clone());
+        for product_id in &product_ids {
+            schema.product_auction_mut().put(product_id, auction_id);
+        }
+        schema.open_auction(&state);
         schema.user_auctions_mut(user.pub_key()).push(auction_id);
 
         Ok(())
@@ -203,8 +709,26 @@ impl Transaction for MakeBid {
             Err(Error::AuctionClosed)?;
         }
 
+        // Check if the bidding period is not over.
+        if schema.height() >= auction_state.ends_at() {
+            Err(Error::BiddingEnded)?;
+        }
+
+        // Check bid quantity.
+        let units = match auction.auction_kind() {
+            AuctionKind::MultiUnit => auction.product_ids().len() as u64,
+            _ => 1,
+        };
+        if self.quantity() == 0 || self.quantity() > units {
+            Err(Error::InvalidBidQuantity)?;
+        }
+
         // Check if the user has enough money.
-        if user.balance() < self.value() {
+        let total_value = self
+            .value()
+            .checked_mul(self.quantity())
+            .ok_or_else(|| Error::InsufficientFunds)?;
+        if user.balance() < total_value {
             Err(Error::InsufficientFunds)?;
         }
 
@@ -213,61 +737,94 @@ impl Transaction for MakeBid {
             Err(Error::NoSelfBidding)?;
         }
 
-        // Get the highest bid.
-        let min_bid = match schema.auction_bids(auction_state.id()).last() {
-            Some(bid) => bid.value(),
-            None => auction.start_price(),
-        };
-
-        // Check if the bid is higher than the highest bid.
-        if min_bid >= self.value() {
-            Err(Error::BidTooLow)?;
+        // Proxy bid makes bids on behalf of the user.
+        if schema.active_proxy_bid(auction_state.id(), user.pub_key()).is_some() {
+            Err(Error::ProxyBidActive)?;
         }
 
-        // Release balance of the previous bidder.
-        if let Some(b) = schema.auction_bids(auction_state.id()).last() {
-            let prev_bid_user = schema.users().get(b.public_key()).unwrap();
-            schema.release_user_balance(prev_bid_user.pub_key(), min_bid);
-        }
+        let bid_value = match auction.auction_kind() {
+            AuctionKind::English => {
+                // Get the highest bid.
+                let highest_bid = schema
+                    .auction_bids(auction_state.id())
+                    .last()
+                    .map(|bid| bid.value());
+
+                // Check if the bid exceeds the highest bid by the minimum increment.
+                let minimum = auction.min_bid(highest_bid);
+                if self.value() < minimum {
+                    Err(Error::BidBelowMinimum { minimum })?;
+                }
+
+                // Release balance of the previous bidder.
+                if let Some(b) = schema.auction_bids(auction_state.id()).last() {
+                    schema.release_outbid(auction_state.id(), &b);
+                }
+
+                self.value()
+            }
+            AuctionKind::Dutch => {
+                // The bidder accepts the current price.
+                let price = auction_state.current_price(schema.height());
+                if price > self.value() {
+                    Err(Error::BidTooLow)?;
+                }
+
+                price
+            }
+            AuctionKind::Sealed | AuctionKind::Reverse => Err(Error::WrongAuctionKind)?,
+            AuctionKind::MultiUnit => {
+                // Bids remain valid until the auction is closed.
+                if self.value() < auction.start_price() {
+                    Err(Error::BidBelowMinimum { minimum: auction.start_price() })?;
+                }
+
+                self.value()
+            }
+        };
 
         // Reserve value in user wallet.
-        schema.reserve_user_balance(user.pub_key(), self.value());
+        schema.reserve_user_balance(user.pub_key(), bid_value * self.quantity());
 
         // Make a bid.
-        let bid = Bid::new(self.public_key(), self.value());
+        let bid = Bid::new(self.public_key(), bid_value, self.quantity());
         schema.auction_bids_mut(self.auction_id()).push(bid);
 
+        // Proxy bidders respond to the bid.
+        schema.resolve_proxy_bids(&auction_state);
+
         // Refresh the auction state.
-        let bids_merkle_root = schema.auction_bids(self.auction_id()).merkle_root();
-        schema.auctions_mut().set(
-            auction_state.id(),
-            AuctionState::new(
-                auction_state.id(),
-                auction,
-                &bids_merkle_root,
-                auction_state.closed(),
-                ),
-                );
+        let auction_state = schema.refresh_auction_state(&auction_state, user.pub_key())?;
 
+        // The first bid wins the Dutch auction.
+        if auction.auction_kind() == AuctionKind::Dutch {
+            schema.settle_auction(&auction_state);
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks if the key is the service key of one of the validator nodes.
+fn check_validator_key(snapshot: &Snapshot, key: &PublicKey) -> ExecutionResult {
+    let keys = Schema::new(&snapshot).actual_configuration().validator_keys;
+    let signed = keys.iter().any(|k| k.service_key == *key);
+    if !signed {
+        Err(Error::UnauthorizedTransaction)?
+    } else {
         Ok(())
     }
 }
 
 impl CloseAuction {
     fn check_signed_by_validator(&self, snapshot: &Snapshot) -> ExecutionResult {
-        let keys = Schema::new(&snapshot).actual_configuration().validator_keys;
-        let signed = keys.iter().any(|k| k.service_key == *self.closing_party());
-        if !signed {
-            Err(Error::UnauthorizedTransaction)?
-        } else {
-            Ok(())
-        }
+        check_validator_key(snapshot, self.closing_party())
     }
 }
 
 impl Transaction for CloseAuction {
     fn verify(&self) -> bool {
-        true
+        self.verify_signature(self.closing_party())
     }
 
     fn execute(&self, fork: &mut Fork) -> ExecutionResult {
@@ -282,51 +839,1556 @@ impl Transaction for CloseAuction {
             .get(self.auction_id())
             .ok_or_else(|| Error::AuctionNotFound)?;
 
+        if auction_state.closed() {
+            Err(Error::AuctionClosed)?;
+        }
+
+        // Auction can be closed only when the bidding (and revealing) period is over.
+        if schema.height() < auction_state.closes_at() {
+            Err(Error::AuctionNotEnded)?;
+        }
+
+        schema.settle_auction(&auction_state);
+        Ok(())
+    }
+}
+
+impl Transaction for CommitBid {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if the user is registered.
+        let user = schema
+            .users()
+            .get(self.public_key())
+            .ok_or_else(|| Error::UserIsNotRegistered)?;
+
+        // Check if the auction exists.
+        let auction_state = schema
+            .auctions()
+            .get(self.auction_id())
+            .ok_or_else(|| Error::AuctionNotFound)?;
+
         let auction = auction_state.auction();
 
-        assert!(!auction_state.closed());
+        if auction.auction_kind() != AuctionKind::Sealed {
+            Err(Error::WrongAuctionKind)?;
+        }
+
+        // Check if the auction is open.
+        if auction_state.closed() {
+            Err(Error::AuctionClosed)?;
+        }
+
+        // Check if the bidding period is not over.
+        if schema.height() >= auction_state.ends_at() {
+            Err(Error::BiddingEnded)?;
+        }
 
-        if let Some(winner_bid) = schema.auction_bids(auction_state.id()).last() {
-            // Decrease winner balance.
-            let winner = schema.users().get(winner_bid.public_key()).unwrap();
-            schema.confirm_user_bid(winner.pub_key(), winner_bid.value());
+        // Bidding in own auction is not allowed.
+        if user.pub_key() == auction.public_key() {
+            Err(Error::NoSelfBidding)?;
+        }
 
-            // Increase seller balance.
-            let seller = schema.users().get(auction.public_key()).unwrap();
-            schema.increase_user_balance(seller.pub_key(), winner_bid.value());
-
-            // Remove product from the seller.
-            schema
-                .user_products_mut(seller.pub_key())
-                .remove(auction.product_id());
-
-            // Pass it to the winner.
-            schema
-                .user_products_mut(winner.pub_key())
-                .insert(*auction.product_id());
-
-            // Change product owner.
-            let product_state = schema.products_states().get(auction.product_id()).unwrap();
-            schema.products_states_mut().put(
-                auction.product_id(),
-                ProductState::new(
-                    product_state.product(),
-                    winner.pub_key(),
-                    ),
-                    );
-        };
+        // Only one bid per user is allowed.
+        if schema.auction_commitments(self.auction_id()).contains(user.pub_key()) {
+            Err(Error::AlreadyCommitted)?;
+        }
+
+        // Check if the user has enough money for the deposit.
+        if user.balance() < auction.deposit() {
+            Err(Error::InsufficientFunds)?;
+        }
+
+        schema.reserve_user_balance(user.pub_key(), auction.deposit());
+
+        let commitment = BidCommitment::new(
+            user.pub_key(),
+            self.commitment(),
+            auction.deposit(),
+            false,
+            0,
+            );
+        schema.auction_commitments_mut(self.auction_id()).put(user.pub_key(), commitment);
+
+        Ok(())
+    }
+}
+
+impl Transaction for RevealBid {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if the user is registered.
+        let user = schema
+            .users()
+            .get(self.public_key())
+            .ok_or_else(|| Error::UserIsNotRegistered)?;
+
+        // Check if the auction exists.
+        let auction_state = schema
+            .auctions()
+            .get(self.auction_id())
+            .ok_or_else(|| Error::AuctionNotFound)?;
+
+        let auction = auction_state.auction();
+
+        if auction.auction_kind() != AuctionKind::Sealed {
+            Err(Error::WrongAuctionKind)?;
+        }
+
+        if auction_state.closed() {
+            Err(Error::AuctionClosed)?;
+        }
+
+        // Bids are revealed after the bidding period only.
+        let height = schema.height();
+        if height < auction_state.ends_at() || height >= auction_state.closes_at() {
+            Err(Error::NotRevealPeriod)?;
+        }
+
+        let commitment = schema
+            .auction_commitments(self.auction_id())
+            .get(user.pub_key())
+            .ok_or_else(|| Error::CommitmentNotFound)?;
+
+        if commitment.revealed() {
+            Err(Error::AlreadyRevealed)?;
+        }
+
+        // Check if the revealed bid matches the commitment.
+        let sealed_bid = SealedBid::new(user.pub_key(), self.auction_id(), self.value(), self.salt());
+        if sealed_bid.hash() != *commitment.commitment() {
+            Err(Error::CommitmentMismatch)?;
+        }
+
+        if self.value() < auction.start_price() {
+            Err(Error::BidTooLow)?;
+        }
+
+        // Reserve the rest of the bid value.
+        if self.value() > commitment.deposit() {
+            let rest = self.value() - commitment.deposit();
+            if user.balance() < rest {
+                Err(Error::InsufficientFunds)?;
+            }
+            schema.reserve_user_balance(user.pub_key(), rest);
+        }
+
+        let commitment = BidCommitment::new(
+            user.pub_key(),
+            commitment.commitment(),
+            commitment.deposit(),
+            true,
+            self.value(),
+            );
+        schema.auction_commitments_mut(self.auction_id()).put(user.pub_key(), commitment);
+
+        // Make the bid public.
+        let bid = Bid::new(self.public_key(), self.value(), 1);
+        schema.auction_bids_mut(self.auction_id()).push(bid);
+
+        // Refresh the auction state.
+        let bids_merkle_root = schema.auction_bids(self.auction_id()).merkle_root();
+        schema.auctions_mut().set(
+            auction_state.id(),
+            AuctionState::new(
+                auction_state.id(),
+                auction,
+                &bids_merkle_root,
+                auction_state.closed(),
+                auction_state.ends_at(),
+                auction_state.extensions_merkle_root(),
+                auction_state.started_at(),
+                auction_state.reserve(),
+                auction_state.reserve_revealed(),
+                auction_state.cancelled(),
+                ),
+                );
+
+        Ok(())
+    }
+}
+
+impl Transaction for RevealReserve {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if the auction exists.
+        let auction_state = schema
+            .auctions()
+            .get(self.auction_id())
+            .ok_or_else(|| Error::AuctionNotFound)?;
+
+        let auction = auction_state.auction();
+
+        // Only the seller may reveal the reserve price.
+        if self.public_key() != auction.public_key() {
+            Err(Error::UnauthorizedTransaction)?;
+        }
+
+        if !auction.has_reserve() {
+            Err(Error::CommitmentNotFound)?;
+        }
+
+        if auction_state.closed() {
+            Err(Error::AuctionClosed)?;
+        }
+
+        // Reserve price is revealed after the bidding period only.
+        let height = schema.height();
+        if height < auction_state.ends_at() || height >= auction_state.closes_at() {
+            Err(Error::NotRevealPeriod)?;
+        }
+
+        if auction_state.reserve_revealed() {
+            Err(Error::AlreadyRevealed)?;
+        }
+
+        // Check if the revealed reserve price matches the commitment.
+        let sealed_reserve = SealedReserve::new(self.public_key(), self.value(), self.salt());
+        if sealed_reserve.hash() != *auction.reserve_hash() {
+            Err(Error::CommitmentMismatch)?;
+        }
+
+        schema.auctions_mut().set(
+            auction_state.id(),
+            AuctionState::new(
+                auction_state.id(),
+                auction_state.auction(),
+                auction_state.bidding_merkle_root(),
+                auction_state.closed(),
+                auction_state.ends_at(),
+                auction_state.extensions_merkle_root(),
+                auction_state.started_at(),
+                self.value(),
+                true,
+                auction_state.cancelled(),
+                ),
+                );
+
+        Ok(())
+    }
+}
+
+impl Transaction for BuyNow {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if the user is registered.
+        let user = schema
+            .users()
+            .get(self.public_key())
+            .ok_or_else(|| Error::UserIsNotRegistered)?;
+
+        // Check if the auction exists.
+        let auction_state = schema
+            .auctions()
+            .get(self.auction_id())
+            .ok_or_else(|| Error::AuctionNotFound)?;
+
+        let auction = auction_state.auction();
+
+        if auction.buy_now_price() == 0 {
+            Err(Error::BuyNowUnavailable)?;
+        }
+
+        // Check if the auction is open.
+        if auction_state.closed() {
+            Err(Error::AuctionClosed)?;
+        }
+
+        // Check if the bidding period is not over.
+        if schema.height() >= auction_state.ends_at() {
+            Err(Error::BiddingEnded)?;
+        }
+
+        // Buying in own auction is not allowed.
+        if user.pub_key() == auction.public_key() {
+            Err(Error::NoSelfBidding)?;
+        }
+
+        // Check if the user has enough money.
+        if user.balance() < auction.buy_now_price() {
+            Err(Error::InsufficientFunds)?;
+        }
+
+        // Buy now is not available once bids reach its price.
+        if let Some(b) = schema.auction_bids(auction_state.id()).last() {
+            if b.value() >= auction.buy_now_price() {
+                Err(Error::BuyNowUnavailable)?;
+            }
+
+            // Release balance of the current highest bidder.
+            schema.release_outbid(auction_state.id(), &b);
+        }
+        schema.close_proxy_bids(auction_state.id(), None);
+
+        // Reserve value in user wallet and make a winning bid.
+        schema.reserve_user_balance(user.pub_key(), auction.buy_now_price());
+        let bid = Bid::new(self.public_key(), auction.buy_now_price(), 1);
+        schema.auction_bids_mut(self.auction_id()).push(bid);
+
+        // Refresh the auction state and settle the auction.
+        let bids_merkle_root = schema.auction_bids(self.auction_id()).merkle_root();
+        let auction_state = AuctionState::new(
+            auction_state.id(),
+            auction,
+            &bids_merkle_root,
+            auction_state.closed(),
+            auction_state.ends_at(),
+            auction_state.extensions_merkle_root(),
+            auction_state.started_at(),
+            auction_state.reserve(),
+            auction_state.reserve_revealed(),
+            auction_state.cancelled(),
+            );
+        schema.settle_auction(&auction_state);
+
+        Ok(())
+    }
+}
+
+impl Transaction for CancelAuction {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if the auction exists.
+        let auction_state = schema
+            .auctions()
+            .get(self.auction_id())
+            .ok_or_else(|| Error::AuctionNotFound)?;
+
+        let auction = auction_state.auction();
+
+        // Only the seller may cancel the auction.
+        if self.public_key() != auction.public_key() {
+            Err(Error::UnauthorizedTransaction)?;
+        }
+
+        if auction_state.closed() {
+            Err(Error::AuctionClosed)?;
+        }
+
+        // The seller can't back out once the final price is known.
+        if schema.height() >= auction_state.ends_at() {
+            Err(Error::BiddingEnded)?;
+        }
+
+        // Sealed bids can't be compared before they are revealed.
+        if schema.auction_commitments(auction_state.id()).keys().next().is_some() {
+            Err(Error::CancellationNotAllowed)?;
+        }
+
+        // All bids of a multi-unit auction may win, there is no single highest bidder.
+        if auction.auction_kind() == AuctionKind::MultiUnit
+            && !schema.auction_bids(auction_state.id()).is_empty()
+        {
+            Err(Error::CancellationNotAllowed)?;
+        }
+
+        // The lowest asking seller of the reverse auction receives the penalty from the buyer.
+        let lowest_ask = schema.auction_asks(auction_state.id()).last();
+
+        // The highest bidder is refunded and receives the penalty from the seller.
+        if let Some(b) = schema.auction_bids(auction_state.id()).last() {
+            let penalty = cancel_penalty(b.value());
+            let seller = schema.users().get(auction.public_key()).unwrap();
+            if seller.balance() < penalty {
+                Err(Error::InsufficientFunds)?;
+            }
+
+            schema.release_outbid(auction_state.id(), &b);
+            schema.decrease_user_balance(seller.pub_key(), penalty);
+            schema.increase_user_balance(b.public_key(), penalty);
+        }
+        schema.close_proxy_bids(auction_state.id(), None);
+
+        schema.cancel_auction(&auction_state);
+
+        // Penalty is paid out of the released budget which can't be lower than the ask.
+        if let Some(ask) = lowest_ask {
+            let penalty = cancel_penalty(ask.value());
+            schema.decrease_user_balance(auction.public_key(), penalty);
+            schema.increase_user_balance(ask.public_key(), penalty);
+        }
+
+        Ok(())
+    }
+}
+
+impl Transaction for PlaceProxyBid {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if the user is registered.
+        let user = schema
+            .users()
+            .get(self.public_key())
+            .ok_or_else(|| Error::UserIsNotRegistered)?;
+
+        // Check if the auction exists.
+        let auction_state = schema
+            .auctions()
+            .get(self.auction_id())
+            .ok_or_else(|| Error::AuctionNotFound)?;
+
+        let auction = auction_state.auction();
+        let auction_id = auction_state.id();
+
+        if auction.auction_kind() != AuctionKind::English {
+            Err(Error::WrongAuctionKind)?;
+        }
+
+        // Check if the auction is open.
+        if auction_state.closed() {
+            Err(Error::AuctionClosed)?;
+        }
+
+        // Check if the bidding period is not over.
+        if schema.height() >= auction_state.ends_at() {
+            Err(Error::BiddingEnded)?;
+        }
+
+        // Bidding in own auction is not allowed.
+        if user.pub_key() == auction.public_key() {
+            Err(Error::NoSelfBidding)?;
+        }
+
+        let top = schema.auction_bids(auction_id).last();
+        let is_top = top.as_ref().map_or(false, |b| b.public_key() == user.pub_key());
+        let proxy = schema.active_proxy_bid(auction_id, user.pub_key());
+
+        // The highest bidder may only raise its bid, others must exceed the highest bid
+        // by the minimum increment.
+        let highest_bid = top.as_ref().map_or(auction.start_price(), |b| b.value());
+        let minimum = match (is_top, proxy.as_ref()) {
+            (_, Some((_, proxy))) => proxy.max_value() + 1,
+            (true, None) => highest_bid + 1,
+            (false, None) => auction.min_bid(top.as_ref().map(|b| b.value())),
+        };
+        if self.max_value() < minimum {
+            Err(Error::BidBelowMinimum { minimum })?;
+        }
+
+        // Funds reserved for the current bid or proxy bid of the user are reused.
+        let reserved = match (is_top, proxy.as_ref()) {
+            (_, Some((_, proxy))) => proxy.max_value(),
+            (true, None) => highest_bid,
+            (false, None) => 0,
+        };
+        if user.balance() + reserved < self.max_value() {
+            Err(Error::InsufficientFunds)?;
+        }
+
+        match proxy {
+            Some((index, _)) => schema.deactivate_proxy_bid(auction_id, index, 0),
+            None if is_top => schema.release_user_balance(user.pub_key(), highest_bid),
+            None => {}
+        }
+
+        // Reserve the maximum in user wallet.
+        schema.reserve_user_balance(user.pub_key(), self.max_value());
+        let proxy = ProxyBid::new(user.pub_key(), self.max_value(), true);
+        schema.auction_proxy_bids_mut(auction_id).push(proxy);
+
+        // Make bids on behalf of proxy bidders.
+        let bids_count = schema.auction_bids(auction_id).len();
+        schema.resolve_proxy_bids(&auction_state);
+        if schema.auction_bids(auction_id).len() > bids_count {
+            schema.refresh_auction_state(&auction_state, user.pub_key())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Transaction for MakeAsk {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if the user is registered.
+        let user = schema
+            .users()
+            .get(self.public_key())
+            .ok_or_else(|| Error::UserIsNotRegistered)?;
+
+        // Check if the auction exists.
+        let auction_state = schema
+            .auctions()
+            .get(self.auction_id())
+            .ok_or_else(|| Error::AuctionNotFound)?;
+
+        let auction = auction_state.auction();
+        let auction_id = auction_state.id();
+
+        if auction.auction_kind() != AuctionKind::Reverse {
+            Err(Error::WrongAuctionKind)?;
+        }
+
+        // Check if the auction is open.
+        if auction_state.closed() {
+            Err(Error::AuctionClosed)?;
+        }
+
+        // Check if the bidding period is not over.
+        if schema.height() >= auction_state.ends_at() {
+            Err(Error::BiddingEnded)?;
+        }
+
+        // Selling to own auction is not allowed.
+        if user.pub_key() == auction.public_key() {
+            Err(Error::NoSelfBidding)?;
+        }
+
+        // Check if product exists.
+        let product = schema
+            .products_states()
+            .get(self.product_id())
+            .ok_or_else(|| Error::ProductNotFound)?;
+
+        // Retired products can't be traded.
+        if product.retired() {
+            Err(Error::ProductRetired)?;
+        }
+
+        // Split product is traded by shares.
+        if product.shares() > 0 {
+            Err(Error::ProductSplit)?;
+        }
+
+        // Check if the user owns the product.
+        if product.owner() != user.pub_key() {
+            Err(Error::ProductNotOwned)?;
+        }
+
+        // Check if the product isn't auctioned or offered already. Product of the lowest ask
+        // is locked by this auction and may be offered again.
+        let offered_here = schema.product_auction().get(self.product_id()) == Some(auction_id);
+        if schema.product_locked(self.product_id()) && !offered_here {
+            Err(Error::ProductAlreadyAuctioned)?;
+        }
+
+        // Check if the ask is within the budget and below the lowest ask.
+        let lowest_ask = schema.auction_asks(auction_id).last();
+        if self.value() > auction.start_price()
+            || lowest_ask.as_ref().map_or(false, |ask| self.value() >= ask.value())
+        {
+            Err(Error::AskTooHigh)?;
+        }
+
+        // Lock the offered product instead of the product of the previous lowest ask.
+        if let Some(ask) = lowest_ask {
+            schema.product_auction_mut().remove(ask.product_id());
+        }
+        schema.product_auction_mut().put(self.product_id(), auction_id);
+
+        // Make an ask.
+        let ask = Ask::new(self.public_key(), self.product_id(), self.value());
+        schema.auction_asks_mut(auction_id).push(ask);
+
+        // Refresh the auction state.
+        schema.refresh_auction_state(&auction_state, user.pub_key())?;
+
+        Ok(())
+    }
+}
+
+impl Transaction for PlaceOrder {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if the user is registered.
+        let user = schema
+            .users()
+            .get(self.public_key())
+            .ok_or_else(|| Error::UserIsNotRegistered)?;
+
+        // Check order parameters.
+        let side = OrderSide::from_u8(self.side()).ok_or_else(|| Error::InvalidOrderParameters)?;
+        let offers_product = *self.product_id() != Hash::zero();
+        if self.price() == 0 || offers_product != (side == OrderSide::Sell) {
+            Err(Error::InvalidOrderParameters)?;
+        }
+
+        match side {
+            OrderSide::Buy => {
+                // Check if the user has enough money.
+                if user.balance() < self.price() {
+                    Err(Error::InsufficientFunds)?;
+                }
+            }
+            OrderSide::Sell => {
+                // Check if product exists.
+                let product = schema
+                    .products_states()
+                    .get(self.product_id())
+                    .ok_or_else(|| Error::ProductNotFound)?;
+
+                // Retired products can't be traded.
+                if product.retired() {
+                    Err(Error::ProductRetired)?;
+                }
+
+                // Split product is traded by shares.
+                if product.shares() > 0 {
+                    Err(Error::ProductSplit)?;
+                }
+
+                // Check if the user owns the product.
+                if product.owner() != user.pub_key() {
+                    Err(Error::ProductNotOwned)?;
+                }
+
+                // Check if the product belongs to the class.
+                if product.product().name() != self.class() {
+                    Err(Error::InvalidOrderParameters)?;
+                }
+
+                // Check if the product isn't auctioned or offered already.
+                if schema.product_locked(self.product_id()) {
+                    Err(Error::ProductAlreadyAuctioned)?;
+                }
+            }
+        }
+
+        let order = Order::new(
+            schema.orders().len(),
+            user.pub_key(),
+            self.class(),
+            self.side(),
+            self.product_id(),
+            self.price(),
+            schema.height(),
+            OrderStatus::Open as u8,
+            );
+
+        // Find the resting order with the highest priority the order can be matched with.
+        // Trading with own orders is not allowed, they are skipped.
+        let class = class_id(self.class());
+        let resting = schema
+            .best_order(&class, side.opposite(), user.pub_key())
+            .filter(|resting| order.crosses(resting));
+
+        // Escrow the price or the product.
+        match side {
+            OrderSide::Buy => schema.reserve_user_balance(user.pub_key(), self.price()),
+            OrderSide::Sell => schema.product_order_mut().put(self.product_id(), order.id()),
+        }
+
+        schema.orders_mut().push(order.clone());
+        schema.user_orders_mut(user.pub_key()).push(order.id());
+
+        // Trade at the price of the resting order, otherwise the order rests in the order book.
+        match (resting, side) {
+            (Some(resting), OrderSide::Buy) => {
+                schema.execute_trade(&order, &resting, resting.price());
+            }
+            (Some(resting), OrderSide::Sell) => {
+                schema.execute_trade(&resting, &order, resting.price());
+            }
+            (None, _) => schema.rest_order(&order),
+        }
+
+        Ok(())
+    }
+}
+
+impl Transaction for CancelOrder {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if the order exists.
+        let order = schema
+            .orders()
+            .get(self.order_id())
+            .ok_or_else(|| Error::OrderNotFound)?;
+
+        // Only the owner may cancel the order.
+        if self.public_key() != order.public_key() {
+            Err(Error::UnauthorizedTransaction)?;
+        }
+
+        if order.order_status() != OrderStatus::Open {
+            Err(Error::OrderClosed)?;
+        }
+
+        // Release escrowed funds, the product is unlocked with the order.
+        if order.order_side() == OrderSide::Buy {
+            schema.release_user_balance(order.public_key(), order.price());
+        }
+        schema.close_order(&order, OrderStatus::Cancelled);
+
+        Ok(())
+    }
+}
+
+impl Transaction for ListForSale {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if the user is registered.
+        let user = schema
+            .users()
+            .get(self.public_key())
+            .ok_or_else(|| Error::UserIsNotRegistered)?;
+
+        // Check if product exists.
+        let product = schema
+            .products_states()
+            .get(self.product_id())
+            .ok_or_else(|| Error::ProductNotFound)?;
+
+        // Retired products can't be traded.
+        if product.retired() {
+            Err(Error::ProductRetired)?;
+        }
+
+        // Split product is traded by shares.
+        if product.shares() > 0 {
+            Err(Error::ProductSplit)?;
+        }
+
+        // Check if the user owns the product.
+        if product.owner() != user.pub_key() {
+            Err(Error::ProductNotOwned)?;
+        }
+
+        // Check if the product isn't auctioned or offered already.
+        if schema.product_locked(self.product_id()) {
+            Err(Error::ProductAlreadyAuctioned)?;
+        }
+
+        if self.price() == 0 {
+            Err(Error::ZeroPrice)?;
+        }
+
+        let listing = Listing::new(user.pub_key(), self.product_id(), self.price(), schema.height());
+        schema.listings_mut().put(self.product_id(), listing);
+
+        Ok(())
+    }
+}
+
+impl Transaction for Delist {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if the product is listed.
+        let listing = schema
+            .listings()
+            .get(self.product_id())
+            .ok_or_else(|| Error::ListingNotFound)?;
+
+        // Only the seller may remove the listing.
+        if self.public_key() != listing.public_key() {
+            Err(Error::UnauthorizedTransaction)?;
+        }
+
+        schema.listings_mut().remove(self.product_id());
+
+        Ok(())
+    }
+}
+
+impl Transaction for Buy {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if the user is registered.
+        let user = schema
+            .users()
+            .get(self.public_key())
+            .ok_or_else(|| Error::UserIsNotRegistered)?;
+
+        // Check if the product is listed.
+        let listing = schema
+            .listings()
+            .get(self.product_id())
+            .ok_or_else(|| Error::ListingNotFound)?;
+
+        // Buying own product is not allowed.
+        if user.pub_key() == listing.public_key() {
+            Err(Error::NoSelfBidding)?;
+        }
+
+        // The price may have been changed by relisting the product.
+        if self.price() != listing.price() {
+            Err(Error::ListingPriceMismatch)?;
+        }
+
+        // Check if the user has enough money.
+        if user.balance() < listing.price() {
+            Err(Error::InsufficientFunds)?;
+        }
+
+        // Pay the seller and pass the product to the buyer.
+        schema.decrease_user_balance(user.pub_key(), listing.price());
+        schema.increase_user_balance(listing.public_key(), listing.price());
+        schema.listings_mut().remove(self.product_id());
+        schema.transfer_product(
+            self.product_id(),
+            user.pub_key(),
+            OwnershipChange::ListingSale,
+            0,
+            listing.price(),
+            );
+
+        Ok(())
+    }
+}
+
+impl Transaction for MakeOffer {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if the user is registered.
+        let user = schema
+            .users()
+            .get(self.public_key())
+            .ok_or_else(|| Error::UserIsNotRegistered)?;
+
+        // Check if product exists.
+        let product = schema
+            .products_states()
+            .get(self.product_id())
+            .ok_or_else(|| Error::ProductNotFound)?;
+
+        // Retired products can't be traded.
+        if product.retired() {
+            Err(Error::ProductRetired)?;
+        }
+
+        // Split product is traded by shares.
+        if product.shares() > 0 {
+            Err(Error::ProductSplit)?;
+        }
+
+        // Offers for own products are not allowed.
+        if product.owner() == user.pub_key() {
+            Err(Error::NoSelfBidding)?;
+        }
+
+        // Auctioned or offered products can be bought through the market only.
+        if schema.product_locked(self.product_id()) {
+            Err(Error::ProductAlreadyAuctioned)?;
+        }
+
+        if self.value() == 0 {
+            Err(Error::ZeroPrice)?;
+        }
+
+        if self.expires_at() <= schema.height() {
+            Err(Error::OfferExpired)?;
+        }
+
+        // Check if the user has enough money.
+        if user.balance() < self.value() {
+            Err(Error::InsufficientFunds)?;
+        }
+
+        // Reserve value in user wallet.
+        schema.reserve_user_balance(user.pub_key(), self.value());
+
+        let offer = Offer::new(
+            schema.offers().len(),
+            user.pub_key(),
+            self.product_id(),
+            self.value(),
+            self.expires_at(),
+            OfferStatus::Pending as u8,
+            );
+        schema.product_offers_mut(self.product_id()).push(offer.id());
+        schema.user_offers_mut(user.pub_key()).push(offer.id());
+        schema.pending_offers_mut().insert(offer.id());
+        schema.offers_mut().push(offer);
+
+        Ok(())
+    }
+}
+
+impl Transaction for AcceptOffer {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if the offer exists.
+        let offer = schema
+            .offers()
+            .get(self.offer_id())
+            .ok_or_else(|| Error::OfferNotFound)?;
+
+        let product = schema
+            .products_states()
+            .get(offer.product_id())
+            .ok_or_else(|| Error::ProductNotFound)?;
+
+        // Only the owner may accept the offer.
+        if self.public_key() != product.owner() {
+            Err(Error::ProductNotOwned)?;
+        }
+
+        // Retired products can't be traded.
+        if product.retired() {
+            Err(Error::ProductRetired)?;
+        }
+
+        // Split product is traded by shares.
+        if product.shares() > 0 {
+            Err(Error::ProductSplit)?;
+        }
+
+        if offer.offer_status() != OfferStatus::Pending {
+            Err(Error::OfferClosed)?;
+        }
+
+        if schema.height() >= offer.expires_at() {
+            Err(Error::OfferExpired)?;
+        }
+
+        // The owner may have bought the product from the user who made the offer.
+        if offer.public_key() == product.owner() {
+            Err(Error::NoSelfBidding)?;
+        }
+
+        // Check if the product isn't auctioned or offered.
+        if schema.product_locked(offer.product_id()) {
+            Err(Error::ProductAlreadyAuctioned)?;
+        }
+
+        // Pay the owner and pass the product to the user who made the offer.
+        schema.confirm_user_bid(offer.public_key(), offer.value());
+        schema.increase_user_balance(product.owner(), offer.value());
+        schema.transfer_product(
+            offer.product_id(),
+            offer.public_key(),
+            OwnershipChange::OfferSale,
+            0,
+            offer.value(),
+            );
+        schema.pending_offers_mut().remove(&offer.id());
+        schema.offers_mut().set(offer.id(), offer.with_status(OfferStatus::Accepted));
+
+        Ok(())
+    }
+}
+
+impl Transaction for RejectOffer {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if the offer exists.
+        let offer = schema
+            .offers()
+            .get(self.offer_id())
+            .ok_or_else(|| Error::OfferNotFound)?;
+
+        let product = schema
+            .products_states()
+            .get(offer.product_id())
+            .ok_or_else(|| Error::ProductNotFound)?;
+
+        // Only the owner may reject the offer.
+        if self.public_key() != product.owner() {
+            Err(Error::ProductNotOwned)?;
+        }
+
+        if offer.offer_status() != OfferStatus::Pending {
+            Err(Error::OfferClosed)?;
+        }
+
+        schema.close_offer(&offer, OfferStatus::Rejected);
+
+        Ok(())
+    }
+}
+
+impl Transaction for WithdrawOffer {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if the offer exists.
+        let offer = schema
+            .offers()
+            .get(self.offer_id())
+            .ok_or_else(|| Error::OfferNotFound)?;
+
+        // Only the user who made the offer may withdraw it.
+        if self.public_key() != offer.public_key() {
+            Err(Error::UnauthorizedTransaction)?;
+        }
+
+        if offer.offer_status() != OfferStatus::Pending {
+            Err(Error::OfferClosed)?;
+        }
+
+        schema.close_offer(&offer, OfferStatus::Withdrawn);
+
+        Ok(())
+    }
+}
+
+impl ExpireOffer {
+    fn check_signed_by_validator(&self, snapshot: &Snapshot) -> ExecutionResult {
+        check_validator_key(snapshot, self.closing_party())
+    }
+}
+
+impl Transaction for ExpireOffer {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.closing_party())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        // Check that the offer is being expired by one of the validator nodes.
+        self.check_signed_by_validator(fork.as_ref())?;
+
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if the offer exists.
+        let offer = schema
+            .offers()
+            .get(self.offer_id())
+            .ok_or_else(|| Error::OfferNotFound)?;
+
+        if offer.offer_status() != OfferStatus::Pending {
+            Err(Error::OfferClosed)?;
+        }
+
+        // Offer can be expired only when it can't be accepted anymore.
+        if schema.height() < offer.expires_at() {
+            Err(Error::OfferNotExpired)?;
+        }
+
+        schema.close_offer(&offer, OfferStatus::Expired);
+
+        Ok(())
+    }
+}
+
+impl Transaction for Transfer {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if both users are registered.
+        let sender = schema
+            .users()
+            .get(self.public_key())
+            .ok_or_else(|| Error::UserIsNotRegistered)?;
+        let receiver = schema
+            .users()
+            .get(self.to())
+            .ok_or_else(|| Error::UserIsNotRegistered)?;
+
+        if sender.pub_key() == receiver.pub_key() {
+            Err(Error::SelfTransfer)?;
+        }
+
+        if self.amount() == 0 {
+            Err(Error::ZeroAmount)?;
+        }
+
+        // Reserved funds can't be transferred.
+        if sender.balance() < self.amount() {
+            Err(Error::InsufficientFunds)?;
+        }
+
+        schema.decrease_user_balance(sender.pub_key(), self.amount());
+        schema.increase_user_balance(receiver.pub_key(), self.amount());
+
+        // Record the transfer in the history of both users.
+        let transfer_id = schema.transfers().len();
+        let record = TransferRecord::new(
+            sender.pub_key(),
+            receiver.pub_key(),
+            self.amount(),
+            schema.height(),
+            &Message::hash(self),
+            );
+        schema.transfers_mut().push(record);
+        schema.user_transfers_mut(sender.pub_key()).push(transfer_id);
+        schema.user_transfers_mut(receiver.pub_key()).push(transfer_id);
+
+        Ok(())
+    }
+}
+
+impl Transaction for TransferProduct {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if both users are registered.
+        let owner = schema
+            .users()
+            .get(self.public_key())
+            .ok_or_else(|| Error::UserIsNotRegistered)?;
+        let receiver = schema
+            .users()
+            .get(self.to())
+            .ok_or_else(|| Error::UserIsNotRegistered)?;
+
+        if owner.pub_key() == receiver.pub_key() {
+            Err(Error::SelfTransfer)?;
+        }
+
+        // Check if product exists.
+        let product = schema
+            .products_states()
+            .get(self.product_id())
+            .ok_or_else(|| Error::ProductNotFound)?;
+
+        // Retired products can't be traded.
+        if product.retired() {
+            Err(Error::ProductRetired)?;
+        }
+
+        // Split product is traded by shares.
+        if product.shares() > 0 {
+            Err(Error::ProductSplit)?;
+        }
+
+        // Check if the user owns the product.
+        if product.owner() != owner.pub_key() {
+            Err(Error::ProductNotOwned)?;
+        }
+
+        // Auctioned or offered product can't be transferred.
+        if schema.product_locked(self.product_id()) {
+            Err(Error::ProductAlreadyAuctioned)?;
+        }
+
+        schema.transfer_product(
+            self.product_id(),
+            receiver.pub_key(),
+            OwnershipChange::Transfer,
+            0,
+            0,
+            );
+
+        Ok(())
+    }
+}
+
+impl Transaction for BurnProduct {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if product exists.
+        let product = schema
+            .products_states()
+            .get(self.product_id())
+            .ok_or_else(|| Error::ProductNotFound)?;
+
+        // Retired products can't be traded.
+        if product.retired() {
+            Err(Error::ProductRetired)?;
+        }
+
+        // Split product is traded by shares.
+        if product.shares() > 0 {
+            Err(Error::ProductSplit)?;
+        }
+
+        // Check if the user owns the product.
+        if product.owner() != self.public_key() {
+            Err(Error::ProductNotOwned)?;
+        }
+
+        // Auctioned or offered product can't be retired.
+        if schema.product_locked(self.product_id()) {
+            Err(Error::ProductAlreadyAuctioned)?;
+        }
+
+        schema.retire_product(self.product_id());
+
+        Ok(())
+    }
+}
+
+impl Transaction for AddCategory {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.admin())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        // Check that the taxonomy is changed by one of the validator nodes.
+        check_validator_key(fork.as_ref(), self.admin())?;
+
+        let mut schema = AuctionSchema::new(fork);
+
+        if self.name().is_empty() {
+            Err(Error::InvalidCategoryName)?;
+        }
+
+        let id = category_id(self.name());
+        if schema.categories().contains(&id) {
+            Err(Error::CategoryAlreadyExists)?;
+        }
+
+        // Parent category must be in the taxonomy.
+        if !self.parent().is_empty() && !schema.categories().contains(&category_id(self.parent())) {
+            Err(Error::CategoryNotFound)?;
+        }
+
+        let category = Category::new(self.name(), self.parent(), self.description());
+        schema.categories_mut().put(&id, category);
+
+        Ok(())
+    }
+}
+
+impl Transaction for RemoveCategory {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.admin())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        // Check that the taxonomy is changed by one of the validator nodes.
+        check_validator_key(fork.as_ref(), self.admin())?;
+
+        let mut schema = AuctionSchema::new(fork);
+
+        let id = category_id(self.name());
+        if !schema.categories().contains(&id) {
+            Err(Error::CategoryNotFound)?;
+        }
+
+        // Category with subcategories or products can't be removed.
+        let has_children = schema
+            .categories()
+            .values()
+            .any(|category| category.parent() == self.name());
+        let has_products = schema.category_products(&id).iter().next().is_some();
+        if has_children || has_products {
+            Err(Error::CategoryInUse)?;
+        }
+
+        schema.categories_mut().remove(&id);
+
+        Ok(())
+    }
+}
+
+impl Transaction for SplitProduct {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if product exists.
+        let product = schema
+            .products_states()
+            .get(self.product_id())
+            .ok_or_else(|| Error::ProductNotFound)?;
+
+        // Retired products can't be traded.
+        if product.retired() {
+            Err(Error::ProductRetired)?;
+        }
+
+        // Check if the user owns the product.
+        if product.owner() != self.public_key() {
+            Err(Error::ProductNotOwned)?;
+        }
+
+        if product.shares() > 0 {
+            Err(Error::ProductSplit)?;
+        }
+
+        // Auctioned or offered product can't be split.
+        if schema.product_locked(self.product_id()) {
+            Err(Error::ProductAlreadyAuctioned)?;
+        }
+
+        if self.shares() < 2 {
+            Err(Error::InvalidShares)?;
+        }
+
+        schema.split_product(self.product_id(), self.shares());
+
+        Ok(())
+    }
+}
+
+impl Transaction for TransferShares {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if the receiver is registered.
+        let receiver = schema
+            .users()
+            .get(self.to())
+            .ok_or_else(|| Error::UserIsNotRegistered)?;
+
+        if self.public_key() == receiver.pub_key() {
+            Err(Error::SelfTransfer)?;
+        }
+
+        // Check if the user holds enough shares.
+        let holding = schema
+            .product_shares(self.product_id())
+            .get(self.public_key())
+            .unwrap_or(0);
+        if self.amount() == 0 {
+            Err(Error::InvalidShares)?;
+        }
+        if holding < self.amount() {
+            Err(Error::InsufficientShares)?;
+        }
+
+        // Shares of the auctioned product are frozen until the auction is over.
+        if schema.product_locked(self.product_id()) {
+            Err(Error::ProductAlreadyAuctioned)?;
+        }
+
+        schema.move_shares(
+            self.product_id(),
+            self.public_key(),
+            receiver.pub_key(),
+            self.amount(),
+            OwnershipChange::ShareTransfer,
+            0,
+            );
+
+        Ok(())
+    }
+}
+
+impl Transaction for ListShares {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Zero amount removes the offer.
+        if self.amount() == 0 {
+            schema.share_listings_mut(self.product_id()).remove(self.public_key());
+            return Ok(());
+        }
+
+        // Check if the user holds enough shares.
+        let holding = schema
+            .product_shares(self.product_id())
+            .get(self.public_key())
+            .unwrap_or(0);
+        if holding < self.amount() {
+            Err(Error::InsufficientShares)?;
+        }
+
+        if self.price() == 0 {
+            Err(Error::ZeroPrice)?;
+        }
+
+        let listing = ShareListing::new(self.public_key(), self.amount(), self.price());
+        schema.share_listings_mut(self.product_id()).put(self.public_key(), listing);
+
+        Ok(())
+    }
+}
+
+impl Transaction for BuyShares {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if the user is registered.
+        let user = schema
+            .users()
+            .get(self.public_key())
+            .ok_or_else(|| Error::UserIsNotRegistered)?;
+
+        // Check if the shares are offered.
+        let listing = schema
+            .share_listings(self.product_id())
+            .get(self.seller())
+            .ok_or_else(|| Error::ListingNotFound)?;
+
+        // Buying own shares is not allowed.
+        if user.pub_key() == listing.public_key() {
+            Err(Error::NoSelfBidding)?;
+        }
+
+        // The price may have been changed by the seller.
+        if self.price() != listing.price() {
+            Err(Error::ListingPriceMismatch)?;
+        }
+
+        // The seller may have transferred the shares after offering them.
+        let holding = schema
+            .product_shares(self.product_id())
+            .get(self.seller())
+            .unwrap_or(0);
+        if self.amount() == 0 {
+            Err(Error::InvalidShares)?;
+        }
+        if self.amount() > listing.amount() || self.amount() > holding {
+            Err(Error::InsufficientShares)?;
+        }
+
+        // Shares of the auctioned product are frozen until the auction is over.
+        if schema.product_locked(self.product_id()) {
+            Err(Error::ProductAlreadyAuctioned)?;
+        }
+
+        // Check if the user has enough money.
+        let total = self
+            .price()
+            .checked_mul(self.amount())
+            .ok_or_else(|| Error::InsufficientFunds)?;
+        if user.balance() < total {
+            Err(Error::InsufficientFunds)?;
+        }
+
+        // Pay the seller and pass the shares to the buyer.
+        schema.decrease_user_balance(user.pub_key(), total);
+        schema.increase_user_balance(self.seller(), total);
+        schema.move_shares(
+            self.product_id(),
+            self.seller(),
+            user.pub_key(),
+            self.amount(),
+            OwnershipChange::ShareSale,
+            total,
+            );
+
+        // Reduce the offered amount.
+        let rest = listing.amount() - self.amount();
+        if rest == 0 {
+            schema.share_listings_mut(self.product_id()).remove(self.seller());
+        } else {
+            let listing = ShareListing::new(self.seller(), rest, listing.price());
+            schema.share_listings_mut(self.product_id()).put(self.seller(), listing);
+        }
+
+        Ok(())
+    }
+}
+
+impl Transaction for MergeShares {
+    fn verify(&self) -> bool {
+        self.verify_signature(self.public_key())
+    }
+
+    fn execute(&self, fork: &mut Fork) -> ExecutionResult {
+        let mut schema = AuctionSchema::new(fork);
+
+        // Check if product exists.
+        let product = schema
+            .products_states()
+            .get(self.product_id())
+            .ok_or_else(|| Error::ProductNotFound)?;
+
+        // Check if the user holds all shares.
+        let holding = schema
+            .product_shares(self.product_id())
+            .get(self.public_key())
+            .unwrap_or(0);
+        if product.shares() == 0 || holding < product.shares() {
+            Err(Error::InsufficientShares)?;
+        }
+
+        // Auctioned product is merged on its sale.
+        if schema.product_locked(self.product_id()) {
+            Err(Error::ProductAlreadyAuctioned)?;
+        }
+
+        schema.clear_shares(self.product_id());
+        if product.owner() != self.public_key() {
+            schema.transfer_product(
+                self.product_id(),
+                self.public_key(),
+                OwnershipChange::Transfer,
+                0,
+                0,
+                );
+        }
 
-        schema.product_auction_mut().remove(auction.product_id());
-        // Close auction
-        schema.auctions_mut().set(
-            auction_state.id(),
-            AuctionState::new(
-                auction_state.id(),
-                auction_state.auction(),
-                auction_state.bidding_merkle_root(),
-                true,
-                ),
-                );
         Ok(())
     }
 }
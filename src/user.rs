@@ -1,6 +1,6 @@
 //! user/wallet structure definition.
 
-use exonum::crypto::PublicKey;
+use exonum::crypto::{Hash, PublicKey};
 
 encoding_struct! {
     /// User information stored in the database.
@@ -16,3 +16,18 @@ encoding_struct! {
     }
 }
 
+encoding_struct! {
+    /// Funds transfer between users.
+    struct TransferRecord {
+        /// Sender.
+        from: &PublicKey,
+        /// Receiver.
+        to: &PublicKey,
+        /// Amount of funds.
+        amount: u64,
+        /// Height of the block at which the transfer was made.
+        height: u64,
+        /// Hash of the transfer transaction.
+        tx_hash: &Hash,
+    }
+}
@@ -0,0 +1,987 @@
+//! Auction transactions tests.
+
+extern crate auction;
+extern crate exonum;
+#[macro_use]
+extern crate exonum_testkit;
+
+mod common;
+
+use exonum::crypto::{self, CryptoHash, Hash};
+use exonum_testkit::{ApiKind, TestKit};
+
+use auction::api::{AuctionInfo, AuctionQuery};
+use auction::auction::{
+    AuctionKind, AuctionState, Bid, DeadlineExtension, IncrementRule, IncrementTier, SealedBid,
+    SealedReserve, Settlement,
+};
+use auction::error::Error;
+use auction::transactions::{
+    BuyNow, CancelAuction, CloseAuction, CommitBid, MakeAsk, MakeBid, PlaceProxyBid, RevealBid,
+    RevealReserve,
+};
+use auction::AuctionSchema;
+
+use common::*;
+
+/// Sealed auction with 5 blocks of bidding and 5 blocks of revealing.
+fn sealed_params(product_ids: Vec<Hash>, start_price: u64) -> AuctionParams {
+    AuctionParams {
+        kind: AuctionKind::Sealed,
+        reveal_duration: 5,
+        deposit: 5,
+        ..AuctionParams::english(product_ids, start_price)
+    }
+}
+
+fn commit_bid(testkit: &mut TestKit, bidder: &Participant, auction_id: u64, value: u64) -> Hash {
+    let salt = crypto::hash(bidder.public_key.as_ref());
+    let commitment = SealedBid::new(&bidder.public_key, auction_id, value, &salt).hash();
+    let tx = CommitBid::new(&bidder.public_key, auction_id, &commitment, &bidder.secret_key);
+    execute(testkit, tx);
+    salt
+}
+
+/// Hash of the reserve price committed by the seller.
+fn reserve_hash(seller: &Participant, value: u64) -> Hash {
+    SealedReserve::new(&seller.public_key, value, &crypto::hash(b"reserve")).hash()
+}
+
+fn reveal_reserve_tx(seller: &Participant, auction_id: u64, value: u64) -> RevealReserve {
+    let salt = crypto::hash(b"reserve");
+    RevealReserve::new(&seller.public_key, auction_id, value, &salt, &seller.secret_key)
+}
+
+fn reveal_bid_tx(bidder: &Participant, auction_id: u64, value: u64, salt: &Hash) -> RevealBid {
+    RevealBid::new(&bidder.public_key, auction_id, value, salt, &bidder.secret_key)
+}
+
+#[test]
+fn test_english_auction_closed_at_end_height() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    let params = AuctionParams::english(vec![product_id], 10);
+    let auction_id = create_auction(&mut testkit, &seller, params);
+
+    // The first bid may be equal to the start price.
+    bid(&mut testkit, &alice, auction_id, 10);
+    bid(&mut testkit, &bob, auction_id, 20);
+    assert_eq!(funds(&testkit, &alice), (100, 0));
+    assert_eq!(funds(&testkit, &bob), (80, 20));
+
+    wait_for_close(&mut testkit, auction_id);
+
+    // The validator closes the auction once its end height is committed.
+    assert!(testkit.height().0 > auction_state(&testkit, auction_id).ends_at());
+    assert_eq!(owner(&testkit, &product_id), bob.public_key);
+    assert_eq!(funds(&testkit, &bob), (80, 0));
+    assert_eq!(funds(&testkit, &alice), (100, 0));
+    assert_eq!(funds(&testkit, &seller), (120, 0));
+    assert!(!AuctionSchema::new(&testkit.snapshot()).product_locked(&product_id));
+}
+
+#[test]
+fn test_auction_without_bids_closed() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    let params = AuctionParams::english(vec![product_id], 10);
+    let auction_id = create_auction(&mut testkit, &seller, params);
+    wait_for_close(&mut testkit, auction_id);
+
+    assert_eq!(owner(&testkit, &product_id), seller.public_key);
+    assert_eq!(funds(&testkit, &seller), (100, 0));
+    assert!(AuctionSchema::new(&testkit.snapshot()).open_auctions().iter().next().is_none());
+}
+
+#[test]
+fn test_close_auction_before_end() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+    let params = AuctionParams { duration: 100, ..AuctionParams::english(vec![product_id], 10) };
+    let auction_id = create_auction(&mut testkit, &seller, params);
+
+    let (public_key, secret_key) = testkit.network().us().service_keypair();
+    let tx = CloseAuction::new(auction_id, &public_key, &secret_key);
+    assert_error(&mut testkit, tx, Error::AuctionNotEnded);
+}
+
+#[test]
+fn test_close_auction_by_user() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+    let params = AuctionParams::english(vec![product_id], 10);
+    let auction_id = create_auction(&mut testkit, &seller, params);
+    wait_for_bidding_end(&mut testkit, auction_id);
+
+    // Only validators may close auctions.
+    let (public_key, secret_key) = crypto::gen_keypair();
+    let tx = CloseAuction::new(auction_id, &public_key, &secret_key);
+    assert_error(&mut testkit, tx, Error::UnauthorizedTransaction);
+}
+
+#[test]
+fn test_auction_end_height_overflow() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    let params = AuctionParams {
+        duration: u64::max_value(),
+        ..AuctionParams::english(vec![product_id], 10)
+    };
+    assert_error(&mut testkit, create_auction_tx(&seller, params), Error::InvalidAuctionParameters);
+}
+
+#[test]
+fn test_late_bid_extends_auction() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    let params = AuctionParams {
+        extension_window: 2,
+        extension: 3,
+        ..AuctionParams::english(vec![product_id], 10)
+    };
+    let auction_id = create_auction(&mut testkit, &seller, params);
+    let ends_at = auction_state(&testkit, auction_id).ends_at();
+
+    // Early bid does not extend the auction.
+    bid(&mut testkit, &alice, auction_id, 10);
+    assert_eq!(auction_state(&testkit, auction_id).ends_at(), ends_at);
+
+    // Bid in the extension window moves the end of the auction.
+    while testkit.height().0 < ends_at - 2 {
+        testkit.create_block();
+    }
+    let height = testkit.height().0;
+    bid(&mut testkit, &bob, auction_id, 20);
+    assert_eq!(auction_state(&testkit, auction_id).ends_at(), ends_at + 3);
+
+    let api = testkit.api();
+    let (state, bids, extensions): (AuctionState, Vec<Bid>, Vec<DeadlineExtension>) = api
+        .public(ApiKind::Service("auction"))
+        .query(&AuctionQuery { id: auction_id })
+        .get::<Option<(AuctionState, Vec<Bid>, Vec<DeadlineExtension>)>>("v1/auction")
+        .unwrap()
+        .unwrap();
+    assert_eq!(state.ends_at(), ends_at + 3);
+    assert_eq!(bids.len(), 2);
+    assert_eq!(extensions.len(), 1);
+    assert_eq!(extensions[0].public_key(), &bob.public_key);
+    assert_eq!(extensions[0].height(), height);
+    assert_eq!(extensions[0].ends_at(), ends_at + 3);
+
+    wait_for_close(&mut testkit, auction_id);
+    assert!(testkit.height().0 > ends_at + 3);
+    assert_eq!(owner(&testkit, &product_id), bob.public_key);
+}
+
+#[test]
+fn test_dutch_auction() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    let params = AuctionParams {
+        kind: AuctionKind::Dutch,
+        duration: 10,
+        price_step: 10,
+        floor_price: 20,
+        ..AuctionParams::english(vec![product_id], 50)
+    };
+    let auction_id = create_auction(&mut testkit, &seller, params);
+    let started_at = auction_state(&testkit, auction_id).started_at();
+
+    // The price drops every block, the bid must reach the current price.
+    assert_eq!(testkit.height().0, started_at + 1);
+    assert_error(&mut testkit, bid_tx(&alice, auction_id, 30), Error::BidTooLow);
+
+    let api = testkit.api();
+    let info: AuctionInfo = api
+        .public(ApiKind::Service("auction"))
+        .query(&AuctionQuery { id: auction_id })
+        .get::<Option<AuctionInfo>>("v1/auction/info")
+        .unwrap()
+        .unwrap();
+    assert_eq!(info.current_price, Some(30));
+
+    // The first bid wins immediately at the current price.
+    bid(&mut testkit, &bob, auction_id, 35);
+    assert!(auction_state(&testkit, auction_id).closed());
+    assert_eq!(owner(&testkit, &product_id), bob.public_key);
+    assert_eq!(funds(&testkit, &bob), (70, 0));
+    assert_eq!(funds(&testkit, &seller), (130, 0));
+    assert_eq!(funds(&testkit, &alice), (100, 0));
+
+    // The price can't drop below the floor price.
+    assert_eq!(auction_state(&testkit, auction_id).current_price(started_at + 100), 20);
+}
+
+#[test]
+fn test_sealed_auction() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let carol = create_user(&mut testkit, "Carol");
+    let product_id = make_product(&mut testkit, &seller, "Vase", "VASE-1", 0);
+
+    let auction_id = create_auction(&mut testkit, &seller, sealed_params(vec![product_id], 10));
+
+    // Bids are committed, not made.
+    assert_error(&mut testkit, bid_tx(&alice, auction_id, 40), Error::WrongAuctionKind);
+
+    let alice_salt = commit_bid(&mut testkit, &alice, auction_id, 40);
+    let bob_salt = commit_bid(&mut testkit, &bob, auction_id, 25);
+    commit_bid(&mut testkit, &carol, auction_id, 30);
+    assert_eq!(funds(&testkit, &alice), (95, 5));
+
+    let commitment = SealedBid::new(&alice.public_key, auction_id, 50, &alice_salt).hash();
+    let tx = CommitBid::new(&alice.public_key, auction_id, &commitment, &alice.secret_key);
+    assert_error(&mut testkit, tx, Error::AlreadyCommitted);
+
+    // Bids are revealed after the bidding period only.
+    let tx = reveal_bid_tx(&alice, auction_id, 40, &alice_salt);
+    assert_error(&mut testkit, tx, Error::NotRevealPeriod);
+
+    wait_for_bidding_end(&mut testkit, auction_id);
+    let tx = reveal_bid_tx(&bob, auction_id, 26, &bob_salt);
+    assert_error(&mut testkit, tx, Error::CommitmentMismatch);
+    execute(&mut testkit, reveal_bid_tx(&alice, auction_id, 40, &alice_salt));
+    execute(&mut testkit, reveal_bid_tx(&bob, auction_id, 25, &bob_salt));
+    assert_eq!(funds(&testkit, &alice), (60, 40));
+    assert_eq!(funds(&testkit, &bob), (75, 25));
+
+    wait_for_close(&mut testkit, auction_id);
+
+    // The highest revealed bid wins, the deposit of the unrevealed bid goes to the seller.
+    assert_eq!(owner(&testkit, &product_id), alice.public_key);
+    assert_eq!(funds(&testkit, &alice), (60, 0));
+    assert_eq!(funds(&testkit, &bob), (100, 0));
+    assert_eq!(funds(&testkit, &carol), (95, 0));
+    assert_eq!(funds(&testkit, &seller), (145, 0));
+}
+
+#[test]
+fn test_sealed_auction_reveal_end_overflow() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let product_id = make_product(&mut testkit, &seller, "Vase", "VASE-1", 0);
+
+    let params = AuctionParams {
+        reveal_duration: u64::max_value(),
+        ..sealed_params(vec![product_id], 10)
+    };
+    assert_error(&mut testkit, create_auction_tx(&seller, params), Error::InvalidAuctionParameters);
+}
+
+#[test]
+fn test_second_price_auction() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let product_id = make_product(&mut testkit, &seller, "Vase", "VASE-1", 0);
+
+    let params = AuctionParams {
+        settlement: Settlement::SecondPrice,
+        ..sealed_params(vec![product_id], 10)
+    };
+    let auction_id = create_auction(&mut testkit, &seller, params);
+    let alice_salt = commit_bid(&mut testkit, &alice, auction_id, 40);
+    let bob_salt = commit_bid(&mut testkit, &bob, auction_id, 25);
+
+    wait_for_bidding_end(&mut testkit, auction_id);
+    execute(&mut testkit, reveal_bid_tx(&alice, auction_id, 40, &alice_salt));
+    execute(&mut testkit, reveal_bid_tx(&bob, auction_id, 25, &bob_salt));
+    wait_for_close(&mut testkit, auction_id);
+
+    // The winner pays the second highest bid.
+    assert_eq!(owner(&testkit, &product_id), alice.public_key);
+    assert_eq!(funds(&testkit, &alice), (75, 0));
+    assert_eq!(funds(&testkit, &bob), (100, 0));
+    assert_eq!(funds(&testkit, &seller), (125, 0));
+}
+
+#[test]
+fn test_second_price_auction_with_single_bid() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let product_id = make_product(&mut testkit, &seller, "Vase", "VASE-1", 0);
+
+    let params = AuctionParams {
+        settlement: Settlement::SecondPrice,
+        ..sealed_params(vec![product_id], 10)
+    };
+    let auction_id = create_auction(&mut testkit, &seller, params);
+    let salt = commit_bid(&mut testkit, &alice, auction_id, 40);
+
+    wait_for_bidding_end(&mut testkit, auction_id);
+    execute(&mut testkit, reveal_bid_tx(&alice, auction_id, 40, &salt));
+    wait_for_close(&mut testkit, auction_id);
+
+    // The only bidder pays the start price.
+    assert_eq!(owner(&testkit, &product_id), alice.public_key);
+    assert_eq!(funds(&testkit, &alice), (90, 0));
+    assert_eq!(funds(&testkit, &seller), (110, 0));
+}
+
+/// English auction with the reserve price revealed during 5 blocks.
+fn reserve_params(seller: &Participant, product_id: Hash, reserve: u64) -> AuctionParams {
+    AuctionParams {
+        reveal_duration: 5,
+        reserve_hash: reserve_hash(seller, reserve),
+        ..AuctionParams::english(vec![product_id], 10)
+    }
+}
+
+#[test]
+fn test_reserve_price_met() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    let auction_id = create_auction(&mut testkit, &seller, reserve_params(&seller, product_id, 30));
+    bid(&mut testkit, &alice, auction_id, 30);
+
+    // Reserve price is revealed by the seller after the bidding period only.
+    assert_error(&mut testkit, reveal_reserve_tx(&seller, auction_id, 30), Error::NotRevealPeriod);
+    wait_for_bidding_end(&mut testkit, auction_id);
+    let tx = reveal_reserve_tx(&alice, auction_id, 30);
+    assert_error(&mut testkit, tx, Error::UnauthorizedTransaction);
+    let tx = reveal_reserve_tx(&seller, auction_id, 20);
+    assert_error(&mut testkit, tx, Error::CommitmentMismatch);
+    execute(&mut testkit, reveal_reserve_tx(&seller, auction_id, 30));
+
+    wait_for_close(&mut testkit, auction_id);
+
+    assert_eq!(owner(&testkit, &product_id), alice.public_key);
+    assert_eq!(funds(&testkit, &alice), (70, 0));
+    assert_eq!(funds(&testkit, &seller), (130, 0));
+}
+
+#[test]
+fn test_reserve_price_not_met() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    let auction_id = create_auction(&mut testkit, &seller, reserve_params(&seller, product_id, 30));
+    bid(&mut testkit, &alice, auction_id, 29);
+    wait_for_bidding_end(&mut testkit, auction_id);
+    execute(&mut testkit, reveal_reserve_tx(&seller, auction_id, 30));
+
+    wait_for_close(&mut testkit, auction_id);
+
+    // Product stays with the seller, the bidder is refunded.
+    assert_eq!(owner(&testkit, &product_id), seller.public_key);
+    assert_eq!(funds(&testkit, &alice), (100, 0));
+    assert_eq!(funds(&testkit, &seller), (100, 0));
+    assert!(!AuctionSchema::new(&testkit.snapshot()).product_locked(&product_id));
+}
+
+#[test]
+fn test_unrevealed_reserve_price() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    let auction_id = create_auction(&mut testkit, &seller, reserve_params(&seller, product_id, 30));
+    bid(&mut testkit, &alice, auction_id, 40);
+    wait_for_close(&mut testkit, auction_id);
+
+    // Product stays with the seller who pays the cancellation penalty to the highest bidder.
+    assert_eq!(owner(&testkit, &product_id), seller.public_key);
+    assert_eq!(funds(&testkit, &alice), (104, 0));
+    assert_eq!(funds(&testkit, &seller), (96, 0));
+}
+
+#[test]
+fn test_sealed_second_price_auction_with_reserve() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let product_id = make_product(&mut testkit, &seller, "Vase", "VASE-1", 0);
+
+    let params = AuctionParams {
+        settlement: Settlement::SecondPrice,
+        reserve_hash: reserve_hash(&seller, 30),
+        ..sealed_params(vec![product_id], 10)
+    };
+    let auction_id = create_auction(&mut testkit, &seller, params);
+    let alice_salt = commit_bid(&mut testkit, &alice, auction_id, 40);
+    let bob_salt = commit_bid(&mut testkit, &bob, auction_id, 20);
+
+    wait_for_bidding_end(&mut testkit, auction_id);
+    execute(&mut testkit, reveal_bid_tx(&alice, auction_id, 40, &alice_salt));
+    execute(&mut testkit, reveal_bid_tx(&bob, auction_id, 20, &bob_salt));
+    execute(&mut testkit, reveal_reserve_tx(&seller, auction_id, 30));
+    wait_for_close(&mut testkit, auction_id);
+
+    // The second highest bid is below the reserve price, so the winner pays the reserve price.
+    assert_eq!(owner(&testkit, &product_id), alice.public_key);
+    assert_eq!(funds(&testkit, &alice), (70, 0));
+    assert_eq!(funds(&testkit, &bob), (100, 0));
+    assert_eq!(funds(&testkit, &seller), (130, 0));
+}
+
+#[test]
+fn test_sealed_auction_with_unrevealed_reserve() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let product_id = make_product(&mut testkit, &seller, "Vase", "VASE-1", 0);
+
+    let params = AuctionParams {
+        settlement: Settlement::SecondPrice,
+        reserve_hash: reserve_hash(&seller, 30),
+        ..sealed_params(vec![product_id], 10)
+    };
+    let auction_id = create_auction(&mut testkit, &seller, params);
+    let salt = commit_bid(&mut testkit, &alice, auction_id, 40);
+
+    wait_for_bidding_end(&mut testkit, auction_id);
+    execute(&mut testkit, reveal_bid_tx(&alice, auction_id, 40, &salt));
+    wait_for_close(&mut testkit, auction_id);
+
+    // The penalty is computed from the highest revealed bid.
+    assert_eq!(owner(&testkit, &product_id), seller.public_key);
+    assert_eq!(funds(&testkit, &alice), (104, 0));
+    assert_eq!(funds(&testkit, &seller), (96, 0));
+}
+
+#[test]
+fn test_buy_now() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    let params = AuctionParams {
+        duration: 100,
+        buy_now_price: 50,
+        ..AuctionParams::english(vec![product_id], 10)
+    };
+    let auction_id = create_auction(&mut testkit, &seller, params);
+    bid(&mut testkit, &alice, auction_id, 20);
+
+    // Buy now closes the auction at once and refunds the highest bidder.
+    execute(&mut testkit, BuyNow::new(&bob.public_key, auction_id, &bob.secret_key));
+    assert!(auction_state(&testkit, auction_id).closed());
+    assert_eq!(owner(&testkit, &product_id), bob.public_key);
+    assert_eq!(funds(&testkit, &bob), (50, 0));
+    assert_eq!(funds(&testkit, &alice), (100, 0));
+    assert_eq!(funds(&testkit, &seller), (150, 0));
+}
+
+#[test]
+fn test_buy_now_after_bids_reach_price() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    let params = AuctionParams {
+        duration: 100,
+        buy_now_price: 50,
+        ..AuctionParams::english(vec![product_id], 10)
+    };
+    let auction_id = create_auction(&mut testkit, &seller, params);
+    bid(&mut testkit, &alice, auction_id, 50);
+
+    let tx = BuyNow::new(&bob.public_key, auction_id, &bob.secret_key);
+    assert_error(&mut testkit, tx, Error::BuyNowUnavailable);
+    assert_eq!(funds(&testkit, &bob), (100, 0));
+}
+
+#[test]
+fn test_buy_now_with_reserve_price() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    // Buy now would close the auction before the reserve price is revealed.
+    let params = AuctionParams {
+        buy_now_price: 50,
+        ..reserve_params(&seller, product_id, 30)
+    };
+    assert_error(&mut testkit, create_auction_tx(&seller, params), Error::InvalidAuctionParameters);
+}
+
+fn cancel_tx(user: &Participant, auction_id: u64) -> CancelAuction {
+    CancelAuction::new(&user.public_key, auction_id, &user.secret_key)
+}
+
+#[test]
+fn test_cancel_auction() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    let params = AuctionParams { duration: 100, ..AuctionParams::english(vec![product_id], 10) };
+    let auction_id = create_auction(&mut testkit, &seller, params);
+    bid(&mut testkit, &alice, auction_id, 40);
+
+    // Only the seller may cancel the auction.
+    assert_error(&mut testkit, cancel_tx(&alice, auction_id), Error::UnauthorizedTransaction);
+
+    // The highest bidder is refunded and receives the penalty.
+    execute(&mut testkit, cancel_tx(&seller, auction_id));
+    let state = auction_state(&testkit, auction_id);
+    assert!(state.closed() && state.cancelled());
+    assert_eq!(owner(&testkit, &product_id), seller.public_key);
+    assert_eq!(funds(&testkit, &alice), (104, 0));
+    assert_eq!(funds(&testkit, &seller), (96, 0));
+    assert!(!AuctionSchema::new(&testkit.snapshot()).product_locked(&product_id));
+
+    assert_error(&mut testkit, bid_tx(&alice, auction_id, 50), Error::AuctionClosed);
+}
+
+#[test]
+fn test_cancel_auction_without_bids() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    let params = AuctionParams { duration: 100, ..AuctionParams::english(vec![product_id], 10) };
+    let auction_id = create_auction(&mut testkit, &seller, params);
+    execute(&mut testkit, cancel_tx(&seller, auction_id));
+
+    assert!(auction_state(&testkit, auction_id).cancelled());
+    assert_eq!(funds(&testkit, &seller), (100, 0));
+}
+
+#[test]
+fn test_cancel_auction_after_bidding_end() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    let auction_id = create_auction(&mut testkit, &seller, reserve_params(&seller, product_id, 30));
+    bid(&mut testkit, &alice, auction_id, 40);
+    wait_for_bidding_end(&mut testkit, auction_id);
+
+    // The seller can't back out once the final price is known.
+    assert_error(&mut testkit, cancel_tx(&seller, auction_id), Error::BiddingEnded);
+}
+
+#[test]
+fn test_cancel_sealed_auction_with_commitments() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let product_id = make_product(&mut testkit, &seller, "Vase", "VASE-1", 0);
+
+    let auction_id = create_auction(&mut testkit, &seller, sealed_params(vec![product_id], 10));
+    commit_bid(&mut testkit, &alice, auction_id, 40);
+
+    assert_error(&mut testkit, cancel_tx(&seller, auction_id), Error::CancellationNotAllowed);
+}
+
+#[test]
+fn test_absolute_increment() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    let params = AuctionParams {
+        increment_rule: IncrementRule::Absolute,
+        increment: 5,
+        ..AuctionParams::english(vec![product_id], 10)
+    };
+    let auction_id = create_auction(&mut testkit, &seller, params);
+
+    // The first bid must reach the start price only.
+    let error = Error::BidBelowMinimum { minimum: 10 };
+    assert_error(&mut testkit, bid_tx(&alice, auction_id, 9), error);
+    bid(&mut testkit, &alice, auction_id, 10);
+
+    let error = Error::BidBelowMinimum { minimum: 15 };
+    assert_error(&mut testkit, bid_tx(&bob, auction_id, 14), error);
+    bid(&mut testkit, &bob, auction_id, 15);
+}
+
+#[test]
+fn test_percent_increment() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    let params = AuctionParams {
+        increment_rule: IncrementRule::Percent,
+        increment: 10,
+        ..AuctionParams::english(vec![product_id], 10)
+    };
+    let auction_id = create_auction(&mut testkit, &seller, params);
+    bid(&mut testkit, &alice, auction_id, 50);
+
+    let error = Error::BidBelowMinimum { minimum: 55 };
+    assert_error(&mut testkit, bid_tx(&bob, auction_id, 54), error);
+    bid(&mut testkit, &bob, auction_id, 55);
+
+    // Percent increment of a huge bid does not overflow.
+    let state = auction_state(&testkit, auction_id);
+    assert_eq!(state.auction().min_next_bid(u64::max_value() - 1), u64::max_value());
+}
+
+#[test]
+fn test_tiered_increment() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    let params = AuctionParams {
+        increment_rule: IncrementRule::Tiered,
+        increment_tiers: vec![IncrementTier::new(0, 1), IncrementTier::new(50, 10)],
+        ..AuctionParams::english(vec![product_id], 10)
+    };
+    let auction_id = create_auction(&mut testkit, &seller, params);
+    bid(&mut testkit, &alice, auction_id, 40);
+    bid(&mut testkit, &bob, auction_id, 50);
+
+    // Increment of the price band of the highest bid is applied.
+    let error = Error::BidBelowMinimum { minimum: 60 };
+    assert_error(&mut testkit, bid_tx(&alice, auction_id, 59), error);
+    bid(&mut testkit, &alice, auction_id, 60);
+}
+
+#[test]
+fn test_invalid_increment_tiers() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    // Tiers must be sorted by the price band.
+    let params = AuctionParams {
+        increment_rule: IncrementRule::Tiered,
+        increment_tiers: vec![IncrementTier::new(50, 10), IncrementTier::new(0, 1)],
+        ..AuctionParams::english(vec![product_id], 10)
+    };
+    assert_error(&mut testkit, create_auction_tx(&seller, params), Error::InvalidAuctionParameters);
+}
+
+fn proxy_bid(testkit: &mut TestKit, bidder: &Participant, auction_id: u64, max_value: u64) {
+    let tx = PlaceProxyBid::new(&bidder.public_key, auction_id, max_value, &bidder.secret_key);
+    execute(testkit, tx);
+}
+
+fn highest_bid(testkit: &TestKit, auction_id: u64) -> Bid {
+    AuctionSchema::new(&testkit.snapshot()).auction_bids(auction_id).last().unwrap()
+}
+
+#[test]
+fn test_proxy_bids() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let carol = create_user(&mut testkit, "Carol");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    let params = AuctionParams {
+        increment_rule: IncrementRule::Absolute,
+        increment: 5,
+        ..AuctionParams::english(vec![product_id], 10)
+    };
+    let auction_id = create_auction(&mut testkit, &seller, params);
+
+    // Proxy bid reserves the maximum and bids the start price.
+    proxy_bid(&mut testkit, &alice, auction_id, 50);
+    assert_eq!(funds(&testkit, &alice), (50, 50));
+    assert_eq!(highest_bid(&testkit, auction_id).value(), 10);
+    assert_error(&mut testkit, bid_tx(&alice, auction_id, 20), Error::ProxyBidActive);
+
+    // Proxy bidder outbids a manual bid by the increment.
+    bid(&mut testkit, &bob, auction_id, 30);
+    let top = highest_bid(&testkit, auction_id);
+    assert_eq!((top.public_key(), top.value()), (&alice.public_key, 35));
+    assert_eq!(funds(&testkit, &bob), (100, 0));
+
+    // The stronger proxy bid wins one increment above the weaker maximum.
+    proxy_bid(&mut testkit, &carol, auction_id, 60);
+    let top = highest_bid(&testkit, auction_id);
+    assert_eq!((top.public_key(), top.value()), (&carol.public_key, 55));
+    assert_eq!(funds(&testkit, &alice), (100, 0));
+
+    wait_for_close(&mut testkit, auction_id);
+
+    // The winner pays its last bid, the rest of the maximum is released.
+    assert_eq!(owner(&testkit, &product_id), carol.public_key);
+    assert_eq!(funds(&testkit, &carol), (45, 0));
+    assert_eq!(funds(&testkit, &alice), (100, 0));
+    assert_eq!(funds(&testkit, &bob), (100, 0));
+    assert_eq!(funds(&testkit, &seller), (155, 0));
+}
+
+#[test]
+fn test_proxy_bid_below_minimum() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let product_id = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    let params = AuctionParams::english(vec![product_id], 10);
+    let auction_id = create_auction(&mut testkit, &seller, params);
+    bid(&mut testkit, &alice, auction_id, 30);
+
+    let tx = PlaceProxyBid::new(&bob.public_key, auction_id, 30, &bob.secret_key);
+    assert_error(&mut testkit, tx, Error::BidBelowMinimum { minimum: 31 });
+}
+
+fn units_bid_tx(bidder: &Participant, auction_id: u64, value: u64, quantity: u64) -> MakeBid {
+    MakeBid::new(&bidder.public_key, auction_id, value, quantity, &bidder.secret_key)
+}
+
+fn make_units(testkit: &mut TestKit, seller: &Participant, count: usize) -> Vec<Hash> {
+    (0..count)
+        .map(|i| make_product(testkit, seller, "Cup", &format!("CUP-{}", i), 0))
+        .collect()
+}
+
+fn multi_unit_params(units: Vec<Hash>, settlement: Settlement) -> AuctionParams {
+    AuctionParams {
+        kind: AuctionKind::MultiUnit,
+        settlement,
+        ..AuctionParams::english(units, 5)
+    }
+}
+
+#[test]
+fn test_multi_unit_auction_uniform_price() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let carol = create_user(&mut testkit, "Carol");
+    let units = make_units(&mut testkit, &seller, 3);
+
+    let params = multi_unit_params(units.clone(), Settlement::Uniform);
+    let auction_id = create_auction(&mut testkit, &seller, params);
+    execute(&mut testkit, units_bid_tx(&alice, auction_id, 10, 2));
+    execute(&mut testkit, units_bid_tx(&bob, auction_id, 8, 2));
+    execute(&mut testkit, units_bid_tx(&carol, auction_id, 6, 1));
+    assert_eq!(funds(&testkit, &alice), (80, 20));
+
+    wait_for_close(&mut testkit, auction_id);
+
+    // Alice wins two units, Bob wins the last one, all winners pay the lowest winning price.
+    assert_eq!(owner(&testkit, &units[0]), alice.public_key);
+    assert_eq!(owner(&testkit, &units[1]), alice.public_key);
+    assert_eq!(owner(&testkit, &units[2]), bob.public_key);
+    assert_eq!(funds(&testkit, &alice), (84, 0));
+    assert_eq!(funds(&testkit, &bob), (92, 0));
+    assert_eq!(funds(&testkit, &carol), (100, 0));
+    assert_eq!(funds(&testkit, &seller), (124, 0));
+}
+
+#[test]
+fn test_multi_unit_auction_first_price() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let units = make_units(&mut testkit, &seller, 3);
+
+    let params = multi_unit_params(units.clone(), Settlement::FirstPrice);
+    let auction_id = create_auction(&mut testkit, &seller, params);
+    execute(&mut testkit, units_bid_tx(&alice, auction_id, 7, 1));
+    execute(&mut testkit, units_bid_tx(&bob, auction_id, 10, 1));
+
+    wait_for_close(&mut testkit, auction_id);
+
+    // Each winner pays its unit price, the unsold unit stays with the seller.
+    assert_eq!(owner(&testkit, &units[0]), bob.public_key);
+    assert_eq!(owner(&testkit, &units[1]), alice.public_key);
+    assert_eq!(owner(&testkit, &units[2]), seller.public_key);
+    assert_eq!(funds(&testkit, &alice), (93, 0));
+    assert_eq!(funds(&testkit, &bob), (90, 0));
+    assert_eq!(funds(&testkit, &seller), (117, 0));
+    assert!(!AuctionSchema::new(&testkit.snapshot()).product_locked(&units[2]));
+}
+
+#[test]
+fn test_multi_unit_bid_quantity() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let units = make_units(&mut testkit, &seller, 3);
+
+    let params = multi_unit_params(units, Settlement::Uniform);
+    let auction_id = create_auction(&mut testkit, &seller, params);
+
+    let tx = units_bid_tx(&alice, auction_id, 10, 0);
+    assert_error(&mut testkit, tx, Error::InvalidBidQuantity);
+    let tx = units_bid_tx(&alice, auction_id, 10, 4);
+    assert_error(&mut testkit, tx, Error::InvalidBidQuantity);
+    let tx = units_bid_tx(&alice, auction_id, 40, 3);
+    assert_error(&mut testkit, tx, Error::InsufficientFunds);
+}
+
+#[test]
+fn test_multi_unit_auction_of_different_products() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let cup = make_product(&mut testkit, &seller, "Cup", "CUP-1", 0);
+    let plate = make_product(&mut testkit, &seller, "Plate", "PLATE-1", 0);
+
+    let params = multi_unit_params(vec![cup, plate], Settlement::Uniform);
+    assert_error(&mut testkit, create_auction_tx(&seller, params), Error::InvalidAuctionParameters);
+}
+
+#[test]
+fn test_bundle_auction() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let lamp = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+    let table = make_product(&mut testkit, &seller, "Table", "TABLE-1", 0);
+
+    let params = AuctionParams::english(vec![lamp, table], 10);
+    let auction_id = create_auction(&mut testkit, &seller, params);
+
+    // Bundled products are locked by the auction.
+    let params = AuctionParams::english(vec![table], 10);
+    assert_error(&mut testkit, create_auction_tx(&seller, params), Error::ProductAlreadyAuctioned);
+
+    bid(&mut testkit, &alice, auction_id, 40);
+    wait_for_close(&mut testkit, auction_id);
+
+    // The winner receives all products at once.
+    assert_eq!(owner(&testkit, &lamp), alice.public_key);
+    assert_eq!(owner(&testkit, &table), alice.public_key);
+    assert_eq!(funds(&testkit, &alice), (60, 0));
+    assert_eq!(funds(&testkit, &seller), (140, 0));
+}
+
+#[test]
+fn test_bundle_auction_with_duplicate_products() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let lamp = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+
+    let params = AuctionParams::english(vec![lamp, lamp], 10);
+    assert_error(&mut testkit, create_auction_tx(&seller, params), Error::InvalidAuctionParameters);
+    let params = AuctionParams::english(vec![], 10);
+    assert_error(&mut testkit, create_auction_tx(&seller, params), Error::InvalidAuctionParameters);
+}
+
+#[test]
+fn test_bundle_auction_of_foreign_product() {
+    let mut testkit = create_testkit();
+    let seller = create_user(&mut testkit, "Seller");
+    let alice = create_user(&mut testkit, "Alice");
+    let lamp = make_product(&mut testkit, &seller, "Lamp", "LAMP-1", 0);
+    let table = make_product(&mut testkit, &alice, "Table", "TABLE-1", 0);
+
+    let params = AuctionParams::english(vec![lamp, table], 10);
+    assert_error(&mut testkit, create_auction_tx(&seller, params), Error::ProductNotOwned);
+}
+
+fn ask_tx(seller: &Participant, auction_id: u64, product_id: &Hash, value: u64) -> MakeAsk {
+    MakeAsk::new(&seller.public_key, auction_id, product_id, value, &seller.secret_key)
+}
+
+fn reverse_params(budget: u64) -> AuctionParams {
+    AuctionParams { kind: AuctionKind::Reverse, ..AuctionParams::english(vec![], budget) }
+}
+
+#[test]
+fn test_reverse_auction() {
+    let mut testkit = create_testkit();
+    let buyer = create_user(&mut testkit, "Buyer");
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let alice_product = make_product(&mut testkit, &alice, "Chair", "CHAIR-1", 0);
+    let bob_product = make_product(&mut testkit, &bob, "Chair", "CHAIR-2", 0);
+
+    // The budget is reserved in the buyer wallet.
+    let auction_id = create_auction(&mut testkit, &buyer, reverse_params(50));
+    assert_eq!(funds(&testkit, &buyer), (50, 50));
+
+    // Asks must be within the budget and below the lowest ask.
+    let tx = ask_tx(&alice, auction_id, &alice_product, 60);
+    assert_error(&mut testkit, tx, Error::AskTooHigh);
+    execute(&mut testkit, ask_tx(&alice, auction_id, &alice_product, 40));
+    let tx = ask_tx(&bob, auction_id, &bob_product, 40);
+    assert_error(&mut testkit, tx, Error::AskTooHigh);
+    execute(&mut testkit, ask_tx(&bob, auction_id, &bob_product, 30));
+    assert_error(&mut testkit, bid_tx(&alice, auction_id, 20), Error::WrongAuctionKind);
+
+    wait_for_close(&mut testkit, auction_id);
+
+    // The lowest ask wins, the rest of the budget is released.
+    assert_eq!(owner(&testkit, &bob_product), buyer.public_key);
+    assert_eq!(owner(&testkit, &alice_product), alice.public_key);
+    assert!(!AuctionSchema::new(&testkit.snapshot()).product_locked(&alice_product));
+    assert_eq!(funds(&testkit, &buyer), (70, 0));
+    assert_eq!(funds(&testkit, &bob), (130, 0));
+    assert_eq!(funds(&testkit, &alice), (100, 0));
+}
+
+#[test]
+fn test_reverse_auction_without_asks() {
+    let mut testkit = create_testkit();
+    let buyer = create_user(&mut testkit, "Buyer");
+
+    let auction_id = create_auction(&mut testkit, &buyer, reverse_params(50));
+    wait_for_close(&mut testkit, auction_id);
+
+    assert_eq!(funds(&testkit, &buyer), (100, 0));
+}
+
+#[test]
+fn test_reverse_auction_budget() {
+    let mut testkit = create_testkit();
+    let buyer = create_user(&mut testkit, "Buyer");
+
+    let tx = create_auction_tx(&buyer, reverse_params(150));
+    assert_error(&mut testkit, tx, Error::InsufficientFunds);
+}
+
+#[test]
+fn test_cancel_reverse_auction() {
+    let mut testkit = create_testkit();
+    let buyer = create_user(&mut testkit, "Buyer");
+    let alice = create_user(&mut testkit, "Alice");
+    let product_id = make_product(&mut testkit, &alice, "Chair", "CHAIR-1", 0);
+
+    let params = AuctionParams { duration: 100, ..reverse_params(50) };
+    let auction_id = create_auction(&mut testkit, &buyer, params);
+    execute(&mut testkit, ask_tx(&alice, auction_id, &product_id, 40));
+    execute(&mut testkit, cancel_tx(&buyer, auction_id));
+
+    // The buyer pays the penalty to the lowest asking seller out of the released budget.
+    assert_eq!(funds(&testkit, &buyer), (96, 0));
+    assert_eq!(funds(&testkit, &alice), (104, 0));
+    assert_eq!(owner(&testkit, &product_id), alice.public_key);
+    assert!(!AuctionSchema::new(&testkit.snapshot()).product_locked(&product_id));
+}
@@ -0,0 +1,198 @@
+//! Helpers shared by the transaction tests.
+
+#![allow(dead_code)]
+
+use exonum::{
+    blockchain::{Schema, Transaction, TransactionErrorType},
+    crypto::{self, CryptoHash, Hash, PublicKey, SecretKey}, messages::Message,
+};
+use exonum_testkit::{TestKit, TestKitBuilder};
+
+use auction::auction::{AuctionKind, AuctionState, IncrementRule, IncrementTier, Settlement};
+use auction::error::Error;
+use auction::product::Product;
+use auction::transactions::{CreateAuction, CreateUser, MakeBid, MakeProduct};
+use auction::{AuctionSchema, Service};
+
+/// Registered user.
+pub struct Participant {
+    pub public_key: PublicKey,
+    pub secret_key: SecretKey,
+}
+
+/// Parameters of the `CreateAuction` transaction.
+pub struct AuctionParams {
+    pub product_ids: Vec<Hash>,
+    pub start_price: u64,
+    pub duration: u64,
+    pub extension_window: u64,
+    pub extension: u64,
+    pub kind: AuctionKind,
+    pub price_step: u64,
+    pub floor_price: u64,
+    pub reveal_duration: u64,
+    pub deposit: u64,
+    pub settlement: Settlement,
+    pub reserve_hash: Hash,
+    pub buy_now_price: u64,
+    pub increment_rule: IncrementRule,
+    pub increment: u64,
+    pub increment_tiers: Vec<IncrementTier>,
+}
+
+impl AuctionParams {
+    /// English auction of the products lasting 5 blocks.
+    pub fn english(product_ids: Vec<Hash>, start_price: u64) -> AuctionParams {
+        AuctionParams {
+            product_ids,
+            start_price,
+            duration: 5,
+            extension_window: 0,
+            extension: 0,
+            kind: AuctionKind::English,
+            price_step: 0,
+            floor_price: 0,
+            reveal_duration: 0,
+            deposit: 0,
+            settlement: Settlement::FirstPrice,
+            reserve_hash: Hash::zero(),
+            buy_now_price: 0,
+            increment_rule: IncrementRule::Any,
+            increment: 0,
+            increment_tiers: vec![],
+        }
+    }
+}
+
+pub fn create_testkit() -> TestKit {
+    TestKitBuilder::validator().with_service(Service).create()
+}
+
+/// Commits the transaction in a separate block and returns its execution result.
+fn commit<T: Transaction + Message>(testkit: &mut TestKit, tx: T) -> Result<(), Option<u8>> {
+    let tx_hash = tx.hash();
+    testkit.create_block_with_transactions(txvec![tx]);
+    let snapshot = testkit.snapshot();
+    let result = Schema::new(&snapshot).transaction_results().get(&tx_hash).unwrap();
+    result.0.map_err(|e| match e.error_type() {
+        TransactionErrorType::Code(code) => Some(code),
+        TransactionErrorType::Panic => None,
+    })
+}
+
+/// Commits the transaction and checks that it is executed successfully.
+pub fn execute<T: Transaction + Message>(testkit: &mut TestKit, tx: T) {
+    let result = commit(testkit, tx);
+    assert!(result.is_ok(), "Transaction failed with code {:?}", result);
+}
+
+/// Commits the transaction and checks that it fails with the given error.
+pub fn assert_error<T: Transaction + Message>(testkit: &mut TestKit, tx: T, error: Error) {
+    assert_eq!(commit(testkit, tx), Err(Some(error.code())));
+}
+
+pub fn create_user(testkit: &mut TestKit, name: &str) -> Participant {
+    let (public_key, secret_key) = crypto::gen_keypair();
+    execute(testkit, CreateUser::new(&public_key, name, &secret_key));
+    Participant { public_key, secret_key }
+}
+
+/// Creates the product with the given barcode and returns its id.
+pub fn make_product(
+    testkit: &mut TestKit,
+    owner: &Participant,
+    name: &str,
+    barcode: &str,
+    royalty_percent: u64,
+) -> Hash {
+    execute(
+        testkit,
+        MakeProduct::new(
+            &owner.public_key,
+            name,
+            barcode,
+            "",
+            "",
+            0,
+            &Hash::zero(),
+            vec![],
+            royalty_percent,
+            &owner.secret_key,
+        ),
+    );
+    Product::new(name, barcode, "", "", 0, &Hash::zero(), vec![]).hash()
+}
+
+pub fn create_auction_tx(seller: &Participant, params: AuctionParams) -> CreateAuction {
+    CreateAuction::new(
+        &seller.public_key,
+        params.product_ids,
+        params.start_price,
+        params.duration,
+        params.extension_window,
+        params.extension,
+        params.kind as u8,
+        params.price_step,
+        params.floor_price,
+        params.reveal_duration,
+        params.deposit,
+        params.settlement as u8,
+        &params.reserve_hash,
+        params.buy_now_price,
+        params.increment_rule as u8,
+        params.increment,
+        params.increment_tiers,
+        &seller.secret_key,
+    )
+}
+
+/// Creates the auction and returns its id.
+pub fn create_auction(testkit: &mut TestKit, seller: &Participant, params: AuctionParams) -> u64 {
+    let auction_id = AuctionSchema::new(&testkit.snapshot()).auctions().len();
+    execute(testkit, create_auction_tx(seller, params));
+    auction_id
+}
+
+pub fn bid_tx(bidder: &Participant, auction_id: u64, value: u64) -> MakeBid {
+    MakeBid::new(&bidder.public_key, auction_id, value, 1, &bidder.secret_key)
+}
+
+pub fn bid(testkit: &mut TestKit, bidder: &Participant, auction_id: u64, value: u64) {
+    execute(testkit, bid_tx(bidder, auction_id, value));
+}
+
+pub fn auction_state(testkit: &TestKit, auction_id: u64) -> AuctionState {
+    AuctionSchema::new(&testkit.snapshot()).auctions().get(auction_id).unwrap()
+}
+
+/// Creates blocks until the bidding period of the auction is over.
+pub fn wait_for_bidding_end(testkit: &mut TestKit, auction_id: u64) {
+    let ends_at = auction_state(testkit, auction_id).ends_at();
+    while testkit.height().0 < ends_at {
+        testkit.create_block();
+    }
+}
+
+/// Creates blocks until the validator closes the auction.
+pub fn wait_for_close(testkit: &mut TestKit, auction_id: u64) {
+    for _ in 0..20 {
+        testkit.create_block();
+        if auction_state(testkit, auction_id).closed() {
+            return;
+        }
+    }
+    panic!("Auction {} is not closed", auction_id);
+}
+
+/// Balance and reserved funds of the user.
+pub fn funds(testkit: &TestKit, user: &Participant) -> (u64, u64) {
+    let snapshot = testkit.snapshot();
+    let user = AuctionSchema::new(&snapshot).users().get(&user.public_key).unwrap();
+    (user.balance(), user.reserved())
+}
+
+pub fn owner(testkit: &TestKit, product_id: &Hash) -> PublicKey {
+    let snapshot = testkit.snapshot();
+    let product_state = AuctionSchema::new(&snapshot).products_states().get(product_id).unwrap();
+    *product_state.owner()
+}
@@ -0,0 +1,367 @@
+//! Order book, listings, offers and transfers tests.
+
+extern crate auction;
+extern crate exonum;
+#[macro_use]
+extern crate exonum_testkit;
+
+mod common;
+
+use exonum::crypto::{self, Hash};
+use exonum::messages::Message;
+use exonum_testkit::TestKit;
+
+use auction::error::Error;
+use auction::offer::OfferStatus;
+use auction::order::{class_id, OrderSide, OrderStatus};
+use auction::transactions::{
+    AcceptOffer, Buy, CancelOrder, Delist, ExpireOffer, ListForSale, MakeOffer, PlaceOrder,
+    RejectOffer, Transfer, WithdrawOffer,
+};
+use auction::AuctionSchema;
+
+use common::*;
+
+fn order_tx(
+    user: &Participant,
+    class: &str,
+    side: OrderSide,
+    product_id: &Hash,
+    price: u64,
+) -> PlaceOrder {
+    PlaceOrder::new(&user.public_key, class, side as u8, product_id, price, &user.secret_key)
+}
+
+/// Places the order and returns its id.
+fn place_order(
+    testkit: &mut TestKit,
+    user: &Participant,
+    side: OrderSide,
+    product_id: &Hash,
+    price: u64,
+) -> u64 {
+    let order_id = AuctionSchema::new(&testkit.snapshot()).orders().len();
+    execute(testkit, order_tx(user, "Chair", side, product_id, price));
+    order_id
+}
+
+fn list_tx(seller: &Participant, product_id: &Hash, price: u64) -> ListForSale {
+    ListForSale::new(&seller.public_key, product_id, price, &seller.secret_key)
+}
+
+fn buy_tx(buyer: &Participant, product_id: &Hash, price: u64) -> Buy {
+    Buy::new(&buyer.public_key, product_id, price, &buyer.secret_key)
+}
+
+/// Makes the offer and returns its id.
+fn make_offer(
+    testkit: &mut TestKit,
+    user: &Participant,
+    product_id: &Hash,
+    value: u64,
+    expires_at: u64,
+) -> u64 {
+    let offer_id = AuctionSchema::new(&testkit.snapshot()).offers().len();
+    let tx = MakeOffer::new(&user.public_key, product_id, value, expires_at, &user.secret_key);
+    execute(testkit, tx);
+    offer_id
+}
+
+fn offer_status(testkit: &TestKit, offer_id: u64) -> OfferStatus {
+    AuctionSchema::new(&testkit.snapshot()).offers().get(offer_id).unwrap().offer_status()
+}
+
+fn transfer_tx(sender: &Participant, to: &Participant, amount: u64, seed: u64) -> Transfer {
+    Transfer::new(&sender.public_key, &to.public_key, amount, seed, &sender.secret_key)
+}
+
+fn order_status(testkit: &TestKit, order_id: u64) -> OrderStatus {
+    AuctionSchema::new(&testkit.snapshot()).orders().get(order_id).unwrap().order_status()
+}
+
+#[test]
+fn test_order_matching() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let carol = create_user(&mut testkit, "Carol");
+    let dave = create_user(&mut testkit, "Dave");
+    let alice_chair = make_product(&mut testkit, &alice, "Chair", "CHAIR-1", 0);
+    let bob_chair = make_product(&mut testkit, &bob, "Chair", "CHAIR-2", 0);
+
+    // Sell orders rest in the order book locking the products.
+    let alice_order = place_order(&mut testkit, &alice, OrderSide::Sell, &alice_chair, 30);
+    let bob_order = place_order(&mut testkit, &bob, OrderSide::Sell, &bob_chair, 25);
+    assert!(AuctionSchema::new(&testkit.snapshot()).product_locked(&alice_chair));
+
+    // Buy order is matched with the lowest ask at its price.
+    let carol_order = place_order(&mut testkit, &carol, OrderSide::Buy, &Hash::zero(), 28);
+    assert_eq!(order_status(&testkit, carol_order), OrderStatus::Filled);
+    assert_eq!(order_status(&testkit, bob_order), OrderStatus::Filled);
+    assert_eq!(order_status(&testkit, alice_order), OrderStatus::Open);
+    assert_eq!(owner(&testkit, &bob_chair), carol.public_key);
+    assert_eq!(funds(&testkit, &carol), (75, 0));
+    assert_eq!(funds(&testkit, &bob), (125, 0));
+
+    let snapshot = testkit.snapshot();
+    let schema = AuctionSchema::new(&snapshot);
+    let trades: Vec<_> = schema.class_trades(&class_id("Chair")).iter().collect();
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].price(), 25);
+    assert_eq!(trades[0].product_id(), &bob_chair);
+
+    // Buy order below the lowest ask rests reserving its price.
+    let dave_order = place_order(&mut testkit, &dave, OrderSide::Buy, &Hash::zero(), 20);
+    assert_eq!(order_status(&testkit, dave_order), OrderStatus::Open);
+    assert_eq!(funds(&testkit, &dave), (80, 20));
+
+    // Cancelled orders release the funds and the products.
+    let tx = CancelOrder::new(&alice.public_key, dave_order, &alice.secret_key);
+    assert_error(&mut testkit, tx, Error::UnauthorizedTransaction);
+    execute(&mut testkit, CancelOrder::new(&dave.public_key, dave_order, &dave.secret_key));
+    execute(&mut testkit, CancelOrder::new(&alice.public_key, alice_order, &alice.secret_key));
+    assert_eq!(order_status(&testkit, alice_order), OrderStatus::Cancelled);
+    assert_eq!(funds(&testkit, &dave), (100, 0));
+    assert!(!AuctionSchema::new(&testkit.snapshot()).product_locked(&alice_chair));
+
+    let tx = CancelOrder::new(&dave.public_key, dave_order, &dave.secret_key);
+    assert_error(&mut testkit, tx, Error::OrderClosed);
+}
+
+#[test]
+fn test_own_orders_skipped() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let alice_chair = make_product(&mut testkit, &alice, "Chair", "CHAIR-1", 0);
+    let bob_chair = make_product(&mut testkit, &bob, "Chair", "CHAIR-2", 0);
+
+    let alice_sell = place_order(&mut testkit, &alice, OrderSide::Sell, &alice_chair, 30);
+    let bob_sell = place_order(&mut testkit, &bob, OrderSide::Sell, &bob_chair, 32);
+
+    // The best crossing order is the own one, the next one is matched.
+    let alice_buy = place_order(&mut testkit, &alice, OrderSide::Buy, &Hash::zero(), 35);
+    assert_eq!(order_status(&testkit, alice_buy), OrderStatus::Filled);
+    assert_eq!(order_status(&testkit, bob_sell), OrderStatus::Filled);
+    assert_eq!(order_status(&testkit, alice_sell), OrderStatus::Open);
+    assert_eq!(owner(&testkit, &bob_chair), alice.public_key);
+    assert_eq!(funds(&testkit, &alice), (68, 0));
+}
+
+#[test]
+fn test_invalid_orders() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let chair = make_product(&mut testkit, &alice, "Chair", "CHAIR-1", 0);
+
+    // Sell order offers an owned product of the class, buy order offers none.
+    let tx = order_tx(&alice, "Table", OrderSide::Sell, &chair, 30);
+    assert_error(&mut testkit, tx, Error::InvalidOrderParameters);
+    let tx = order_tx(&alice, "Chair", OrderSide::Buy, &chair, 30);
+    assert_error(&mut testkit, tx, Error::InvalidOrderParameters);
+    let tx = order_tx(&alice, "Chair", OrderSide::Sell, &chair, 0);
+    assert_error(&mut testkit, tx, Error::InvalidOrderParameters);
+    let tx = order_tx(&bob, "Chair", OrderSide::Sell, &chair, 30);
+    assert_error(&mut testkit, tx, Error::ProductNotOwned);
+    let tx = order_tx(&bob, "Chair", OrderSide::Buy, &Hash::zero(), 300);
+    assert_error(&mut testkit, tx, Error::InsufficientFunds);
+
+    // Auctioned product can't be offered.
+    let params = AuctionParams::english(vec![chair], 10);
+    create_auction(&mut testkit, &alice, params);
+    let tx = order_tx(&alice, "Chair", OrderSide::Sell, &chair, 30);
+    assert_error(&mut testkit, tx, Error::ProductAlreadyAuctioned);
+}
+
+#[test]
+fn test_listing_sale() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let chair = make_product(&mut testkit, &alice, "Chair", "CHAIR-1", 0);
+
+    execute(&mut testkit, list_tx(&alice, &chair, 40));
+    assert_eq!(AuctionSchema::new(&testkit.snapshot()).listings().get(&chair).unwrap().price(), 40);
+
+    // Listed product is locked for other sales.
+    assert_error(&mut testkit, list_tx(&alice, &chair, 50), Error::ProductAlreadyAuctioned);
+    let params = AuctionParams::english(vec![chair], 10);
+    assert_error(&mut testkit, create_auction_tx(&alice, params), Error::ProductAlreadyAuctioned);
+
+    // The buyer agrees to the listed price.
+    assert_error(&mut testkit, buy_tx(&alice, &chair, 40), Error::NoSelfBidding);
+    assert_error(&mut testkit, buy_tx(&bob, &chair, 30), Error::ListingPriceMismatch);
+    execute(&mut testkit, buy_tx(&bob, &chair, 40));
+    assert_eq!(owner(&testkit, &chair), bob.public_key);
+    assert_eq!(funds(&testkit, &alice), (140, 0));
+    assert_eq!(funds(&testkit, &bob), (60, 0));
+    assert!(!AuctionSchema::new(&testkit.snapshot()).listings().contains(&chair));
+
+    assert_error(&mut testkit, buy_tx(&bob, &chair, 40), Error::ListingNotFound);
+}
+
+#[test]
+fn test_delist() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let chair = make_product(&mut testkit, &alice, "Chair", "CHAIR-1", 0);
+
+    assert_error(&mut testkit, list_tx(&bob, &chair, 40), Error::ProductNotOwned);
+    assert_error(&mut testkit, list_tx(&alice, &chair, 0), Error::ZeroPrice);
+    execute(&mut testkit, list_tx(&alice, &chair, 400));
+    assert_error(&mut testkit, buy_tx(&bob, &chair, 400), Error::InsufficientFunds);
+
+    // Only the seller removes the listing, then the product may be relisted.
+    let tx = Delist::new(&bob.public_key, &chair, &bob.secret_key);
+    assert_error(&mut testkit, tx, Error::UnauthorizedTransaction);
+    execute(&mut testkit, Delist::new(&alice.public_key, &chair, &alice.secret_key));
+    assert_error(&mut testkit, buy_tx(&bob, &chair, 400), Error::ListingNotFound);
+    execute(&mut testkit, list_tx(&alice, &chair, 40));
+    assert_eq!(AuctionSchema::new(&testkit.snapshot()).listings().get(&chair).unwrap().price(), 40);
+}
+
+#[test]
+fn test_offers() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let carol = create_user(&mut testkit, "Carol");
+    let chair = make_product(&mut testkit, &alice, "Chair", "CHAIR-1", 0);
+    let expires_at = testkit.height().0 + 100;
+
+    let bob_offer = make_offer(&mut testkit, &bob, &chair, 30, expires_at);
+    let carol_offer = make_offer(&mut testkit, &carol, &chair, 40, expires_at);
+    assert_eq!(funds(&testkit, &bob), (70, 30));
+
+    // Only the user who made the offer withdraws it, only the owner rejects or accepts it.
+    let tx = WithdrawOffer::new(&alice.public_key, bob_offer, &alice.secret_key);
+    assert_error(&mut testkit, tx, Error::UnauthorizedTransaction);
+    let tx = RejectOffer::new(&bob.public_key, bob_offer, &bob.secret_key);
+    assert_error(&mut testkit, tx, Error::ProductNotOwned);
+    let tx = AcceptOffer::new(&carol.public_key, carol_offer, &carol.secret_key);
+    assert_error(&mut testkit, tx, Error::ProductNotOwned);
+
+    execute(&mut testkit, RejectOffer::new(&alice.public_key, bob_offer, &alice.secret_key));
+    assert_eq!(offer_status(&testkit, bob_offer), OfferStatus::Rejected);
+    assert_eq!(funds(&testkit, &bob), (100, 0));
+    let tx = AcceptOffer::new(&alice.public_key, bob_offer, &alice.secret_key);
+    assert_error(&mut testkit, tx, Error::OfferClosed);
+
+    execute(&mut testkit, AcceptOffer::new(&alice.public_key, carol_offer, &alice.secret_key));
+    assert_eq!(offer_status(&testkit, carol_offer), OfferStatus::Accepted);
+    assert_eq!(owner(&testkit, &chair), carol.public_key);
+    assert_eq!(funds(&testkit, &alice), (140, 0));
+    assert_eq!(funds(&testkit, &carol), (60, 0));
+    assert!(!AuctionSchema::new(&testkit.snapshot()).pending_offers().contains(&carol_offer));
+
+    // Offer of the new owner is withdrawn.
+    let alice_offer = make_offer(&mut testkit, &alice, &chair, 50, expires_at);
+    execute(&mut testkit, WithdrawOffer::new(&alice.public_key, alice_offer, &alice.secret_key));
+    assert_eq!(offer_status(&testkit, alice_offer), OfferStatus::Withdrawn);
+    assert_eq!(funds(&testkit, &alice), (140, 0));
+}
+
+#[test]
+fn test_invalid_offers() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let chair = make_product(&mut testkit, &alice, "Chair", "CHAIR-1", 0);
+    let height = testkit.height().0;
+
+    let tx = MakeOffer::new(&alice.public_key, &chair, 30, height + 10, &alice.secret_key);
+    assert_error(&mut testkit, tx, Error::NoSelfBidding);
+    let tx = MakeOffer::new(&bob.public_key, &chair, 0, height + 10, &bob.secret_key);
+    assert_error(&mut testkit, tx, Error::ZeroPrice);
+    let tx = MakeOffer::new(&bob.public_key, &chair, 30, height, &bob.secret_key);
+    assert_error(&mut testkit, tx, Error::OfferExpired);
+    let tx = MakeOffer::new(&bob.public_key, &chair, 300, height + 10, &bob.secret_key);
+    assert_error(&mut testkit, tx, Error::InsufficientFunds);
+}
+
+#[test]
+fn test_offer_expiry() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let chair = make_product(&mut testkit, &alice, "Chair", "CHAIR-1", 0);
+    let expires_at = testkit.height().0 + 3;
+    let offer_id = make_offer(&mut testkit, &bob, &chair, 30, expires_at);
+
+    // Validators expire only the offers which can't be accepted anymore.
+    let (public_key, secret_key) = testkit.network().us().service_keypair();
+    let tx = ExpireOffer::new(offer_id, &public_key, &secret_key);
+    assert_error(&mut testkit, tx, Error::OfferNotExpired);
+    let (public_key, secret_key) = crypto::gen_keypair();
+    let tx = ExpireOffer::new(offer_id, &public_key, &secret_key);
+    assert_error(&mut testkit, tx, Error::UnauthorizedTransaction);
+
+    while testkit.height().0 < expires_at {
+        testkit.create_block();
+    }
+    let tx = AcceptOffer::new(&alice.public_key, offer_id, &alice.secret_key);
+    assert_error(&mut testkit, tx, Error::OfferExpired);
+
+    // The expired offer is closed by the validator releasing its value.
+    for _ in 0..5 {
+        if offer_status(&testkit, offer_id) == OfferStatus::Expired {
+            break;
+        }
+        testkit.create_block();
+    }
+    assert_eq!(offer_status(&testkit, offer_id), OfferStatus::Expired);
+    assert_eq!(funds(&testkit, &bob), (100, 0));
+    assert!(!AuctionSchema::new(&testkit.snapshot()).pending_offers().contains(&offer_id));
+}
+
+#[test]
+fn test_transfer() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+
+    let tx = transfer_tx(&alice, &bob, 30, 0);
+    let tx_hash = Message::hash(&tx);
+    execute(&mut testkit, tx);
+    execute(&mut testkit, transfer_tx(&bob, &alice, 10, 0));
+    assert_eq!(funds(&testkit, &alice), (80, 0));
+    assert_eq!(funds(&testkit, &bob), (120, 0));
+
+    // Transfers are recorded in the history of both users.
+    let snapshot = testkit.snapshot();
+    let schema = AuctionSchema::new(&snapshot);
+    let record = schema.transfers().get(0).unwrap();
+    assert_eq!(record.from(), &alice.public_key);
+    assert_eq!(record.to(), &bob.public_key);
+    assert_eq!(record.amount(), 30);
+    assert_eq!(record.tx_hash(), &tx_hash);
+    let alice_transfers: Vec<_> = schema.user_transfers(&alice.public_key).iter().collect();
+    let bob_transfers: Vec<_> = schema.user_transfers(&bob.public_key).iter().collect();
+    assert_eq!(alice_transfers, vec![0, 1]);
+    assert_eq!(bob_transfers, vec![0, 1]);
+}
+
+#[test]
+fn test_invalid_transfers() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let chair = make_product(&mut testkit, &alice, "Chair", "CHAIR-1", 0);
+
+    assert_error(&mut testkit, transfer_tx(&alice, &alice, 30, 0), Error::SelfTransfer);
+    assert_error(&mut testkit, transfer_tx(&alice, &bob, 0, 0), Error::ZeroAmount);
+    assert_error(&mut testkit, transfer_tx(&alice, &bob, 101, 0), Error::InsufficientFunds);
+
+    // Reserved funds can't be transferred.
+    let expires_at = testkit.height().0 + 10;
+    make_offer(&mut testkit, &bob, &chair, 80, expires_at);
+    assert_error(&mut testkit, transfer_tx(&bob, &alice, 30, 0), Error::InsufficientFunds);
+
+    let (public_key, secret_key) = crypto::gen_keypair();
+    let tx = Transfer::new(&alice.public_key, &public_key, 30, 0, &alice.secret_key);
+    assert_error(&mut testkit, tx, Error::UserIsNotRegistered);
+    let tx = Transfer::new(&public_key, &alice.public_key, 30, 0, &secret_key);
+    assert_error(&mut testkit, tx, Error::UserIsNotRegistered);
+}
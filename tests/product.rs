@@ -0,0 +1,659 @@
+//! Product ownership, history, metadata and taxonomy tests.
+
+extern crate auction;
+extern crate exonum;
+#[macro_use]
+extern crate exonum_testkit;
+
+mod common;
+
+use exonum::crypto::{self, CryptoHash, Hash};
+use exonum_testkit::{ApiKind, TestKit};
+
+use auction::api::{
+    BarcodeQuery, CategoryQuery, ProductHistoryInfo, ProductQuery, ProductSearchQuery,
+};
+use auction::auction::AuctionState;
+use auction::category::{category_id, Category};
+use auction::error::Error;
+use auction::product::{OwnershipChange, Product, ProductAttribute, ProductCondition, ProductState};
+use auction::transactions::{
+    AddCategory, BurnProduct, Buy, BuyShares, ListForSale, ListShares, MakeProduct, MergeShares,
+    RemoveCategory, SplitProduct, TransferProduct, TransferShares,
+};
+use auction::AuctionSchema;
+
+use common::*;
+
+fn transfer_product_tx(
+    owner: &Participant,
+    to: &Participant,
+    product_id: &Hash,
+) -> TransferProduct {
+    TransferProduct::new(&owner.public_key, &to.public_key, product_id, 0, &owner.secret_key)
+}
+
+fn burn_tx(owner: &Participant, product_id: &Hash) -> BurnProduct {
+    BurnProduct::new(&owner.public_key, product_id, &owner.secret_key)
+}
+
+/// Metadata of the product made by the test.
+struct Metadata {
+    name: &'static str,
+    barcode: &'static str,
+    description: &'static str,
+    category: &'static str,
+    condition: u8,
+    content_hash: Hash,
+    attributes: Vec<ProductAttribute>,
+}
+
+impl Metadata {
+    fn new(name: &'static str, barcode: &'static str) -> Metadata {
+        Metadata {
+            name,
+            barcode,
+            description: "",
+            category: "",
+            condition: ProductCondition::New as u8,
+            content_hash: Hash::zero(),
+            attributes: vec![],
+        }
+    }
+
+    fn product(&self) -> Product {
+        Product::new(
+            self.name,
+            self.barcode,
+            self.description,
+            self.category,
+            self.condition,
+            &self.content_hash,
+            self.attributes.clone(),
+        )
+    }
+}
+
+fn make_product_tx(owner: &Participant, metadata: &Metadata, royalty_percent: u64) -> MakeProduct {
+    MakeProduct::new(
+        &owner.public_key,
+        metadata.name,
+        metadata.barcode,
+        metadata.description,
+        metadata.category,
+        metadata.condition,
+        &metadata.content_hash,
+        metadata.attributes.clone(),
+        royalty_percent,
+        &owner.secret_key,
+    )
+}
+
+fn attribute(key: &str, value: &str) -> ProductAttribute {
+    ProductAttribute::new(key, value)
+}
+
+/// Names of the products found by the search API.
+fn search(testkit: &TestKit, query: &ProductSearchQuery) -> Vec<String> {
+    let products: Vec<ProductState> = testkit
+        .api()
+        .public(ApiKind::Service("auction"))
+        .query(query)
+        .get("v1/products/search")
+        .unwrap();
+    let mut names: Vec<_> = products
+        .iter()
+        .map(|state| state.product().name().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+fn search_query() -> ProductSearchQuery {
+    ProductSearchQuery { text: None, category: None, condition: None, key: None, value: None }
+}
+
+fn add_category_tx(testkit: &TestKit, name: &str, parent: &str, seed: u64) -> AddCategory {
+    let (public_key, secret_key) = testkit.network().us().service_keypair();
+    AddCategory::new(&public_key, name, parent, "", seed, &secret_key)
+}
+
+fn remove_category_tx(testkit: &TestKit, name: &str, seed: u64) -> RemoveCategory {
+    let (public_key, secret_key) = testkit.network().us().service_keypair();
+    RemoveCategory::new(&public_key, name, seed, &secret_key)
+}
+
+fn split_tx(owner: &Participant, product_id: &Hash, shares: u64) -> SplitProduct {
+    SplitProduct::new(&owner.public_key, product_id, shares, &owner.secret_key)
+}
+
+fn transfer_shares_tx(
+    holder: &Participant,
+    to: &Participant,
+    product_id: &Hash,
+    amount: u64,
+    seed: u64,
+) -> TransferShares {
+    TransferShares::new(
+        &holder.public_key,
+        &to.public_key,
+        product_id,
+        amount,
+        seed,
+        &holder.secret_key,
+    )
+}
+
+fn shares(testkit: &TestKit, holder: &Participant, product_id: &Hash) -> u64 {
+    let snapshot = testkit.snapshot();
+    let holding = AuctionSchema::new(&snapshot).product_shares(product_id).get(&holder.public_key);
+    holding.unwrap_or(0)
+}
+
+/// Kinds of the ownership changes recorded in the product history.
+fn history(testkit: &TestKit, product_id: &Hash) -> Vec<OwnershipChange> {
+    let snapshot = testkit.snapshot();
+    let schema = AuctionSchema::new(&snapshot);
+    let changes = schema.product_history(product_id).iter().map(|record| record.change()).collect();
+    changes
+}
+
+#[test]
+fn test_transfer_product() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let chair = make_product(&mut testkit, &alice, "Chair", "CHAIR-1", 0);
+
+    execute(&mut testkit, transfer_product_tx(&alice, &bob, &chair));
+    assert_eq!(owner(&testkit, &chair), bob.public_key);
+    let changes = vec![OwnershipChange::Creation, OwnershipChange::Transfer];
+    assert_eq!(history(&testkit, &chair), changes);
+
+    let snapshot = testkit.snapshot();
+    let schema = AuctionSchema::new(&snapshot);
+    assert!(!schema.user_products(&alice.public_key).contains(&chair));
+    assert!(schema.user_products(&bob.public_key).contains(&chair));
+    assert_eq!(funds(&testkit, &alice), (100, 0));
+    assert_eq!(funds(&testkit, &bob), (100, 0));
+}
+
+#[test]
+fn test_invalid_product_transfers() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let chair = make_product(&mut testkit, &alice, "Chair", "CHAIR-1", 0);
+
+    assert_error(&mut testkit, transfer_product_tx(&alice, &alice, &chair), Error::SelfTransfer);
+    assert_error(&mut testkit, transfer_product_tx(&bob, &alice, &chair), Error::ProductNotOwned);
+    let tx = transfer_product_tx(&alice, &bob, &Hash::zero());
+    assert_error(&mut testkit, tx, Error::ProductNotFound);
+
+    // Auctioned product is passed to the winner only.
+    let params = AuctionParams::english(vec![chair], 10);
+    create_auction(&mut testkit, &alice, params);
+    let tx = transfer_product_tx(&alice, &bob, &chair);
+    assert_error(&mut testkit, tx, Error::ProductAlreadyAuctioned);
+}
+
+#[test]
+fn test_burn_product() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let chair = make_product(&mut testkit, &alice, "Chair", "CHAIR-1", 0);
+
+    assert_error(&mut testkit, burn_tx(&bob, &chair), Error::ProductNotOwned);
+    execute(&mut testkit, burn_tx(&alice, &chair));
+
+    // Retired product state and history are kept, the last owner is recorded.
+    let snapshot = testkit.snapshot();
+    let schema = AuctionSchema::new(&snapshot);
+    let product_state = schema.products_states().get(&chair).unwrap();
+    assert!(product_state.retired());
+    assert_eq!(product_state.owner(), &alice.public_key);
+    assert!(!schema.user_products(&alice.public_key).contains(&chair));
+    let changes = vec![OwnershipChange::Creation, OwnershipChange::Retirement];
+    assert_eq!(history(&testkit, &chair), changes);
+
+    // Retired product can't be traded anymore.
+    assert_error(&mut testkit, burn_tx(&alice, &chair), Error::ProductRetired);
+    let tx = transfer_product_tx(&alice, &bob, &chair);
+    assert_error(&mut testkit, tx, Error::ProductRetired);
+    let params = AuctionParams::english(vec![chair], 10);
+    assert_error(&mut testkit, create_auction_tx(&alice, params), Error::ProductRetired);
+}
+
+#[test]
+fn test_burn_auctioned_product() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+    let chair = make_product(&mut testkit, &alice, "Chair", "CHAIR-1", 0);
+    let params = AuctionParams::english(vec![chair], 10);
+    let auction_id = create_auction(&mut testkit, &alice, params);
+
+    assert_error(&mut testkit, burn_tx(&alice, &chair), Error::ProductAlreadyAuctioned);
+
+    // Product unsold in the auction may be retired.
+    wait_for_close(&mut testkit, auction_id);
+    execute(&mut testkit, burn_tx(&alice, &chair));
+}
+
+#[test]
+fn test_product_history() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let carol = create_user(&mut testkit, "Carol");
+    let chair = make_product(&mut testkit, &alice, "Chair", "CHAIR-1", 0);
+
+    execute(&mut testkit, transfer_product_tx(&alice, &bob, &chair));
+    let auction_id = create_auction(&mut testkit, &bob, AuctionParams::english(vec![chair], 10));
+    bid(&mut testkit, &carol, auction_id, 30);
+    wait_for_close(&mut testkit, auction_id);
+
+    let snapshot = testkit.snapshot();
+    let schema = AuctionSchema::new(&snapshot);
+    let records: Vec<_> = schema.product_history(&chair).iter().collect();
+    let changes: Vec<_> = records.iter().map(|record| record.change()).collect();
+    let expected = vec![
+        OwnershipChange::Creation,
+        OwnershipChange::Transfer,
+        OwnershipChange::AuctionSale,
+    ];
+    assert_eq!(changes, expected);
+    assert_eq!(records[1].from(), &alice.public_key);
+    assert_eq!(records[1].to(), &bob.public_key);
+    assert_eq!(records[1].sale_auction(), None);
+    assert_eq!(records[2].to(), &carol.public_key);
+    assert_eq!(records[2].sale_auction(), Some(auction_id));
+    assert_eq!(records[2].price(), 30);
+
+    // The product state commits to the history.
+    let product_state = schema.products_states().get(&chair).unwrap();
+    let history_merkle_root = schema.product_history(&chair).merkle_root();
+    assert_eq!(product_state.history_merkle_root(), &history_merkle_root);
+}
+
+#[test]
+fn test_product_history_proofs() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let chair = make_product(&mut testkit, &alice, "Chair", "CHAIR-1", 0);
+    execute(&mut testkit, transfer_product_tx(&alice, &bob, &chair));
+
+    let info: ProductHistoryInfo = testkit
+        .api()
+        .public(ApiKind::Service("auction"))
+        .query(&ProductQuery { id: chair })
+        .get("v1/product/history")
+        .unwrap();
+
+    // Proofs lead from the state hash of the block to the product state.
+    let state_hash = *info.block_proof.block.state_hash();
+    let to_table = info.to_table.check().unwrap();
+    assert_eq!(to_table.merkle_root(), state_hash);
+    let to_product = info.to_product.check().unwrap();
+    assert!(to_table.entries().iter().any(|&(_, root)| *root == to_product.merkle_root()));
+    let product_state = to_product
+        .entries()
+        .iter()
+        .find(|&&(product_id, _)| *product_id == chair)
+        .map(|&(_, product_state)| product_state.clone())
+        .unwrap();
+    assert_eq!(product_state.owner(), &bob.public_key);
+
+    // History proof is checked against the root stored in the product state.
+    let history = info.history.unwrap();
+    let records = history
+        .proof
+        .validate(*product_state.history_merkle_root(), history.records.len() as u64)
+        .unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[1].1, &history.records[1]);
+    assert_eq!(history.records[1].change(), OwnershipChange::Transfer);
+
+    // Unknown products have no history.
+    let info: ProductHistoryInfo = testkit
+        .api()
+        .public(ApiKind::Service("auction"))
+        .query(&ProductQuery { id: Hash::zero() })
+        .get("v1/product/history")
+        .unwrap();
+    assert!(info.history.is_none());
+    assert!(info.to_product.check().unwrap().entries().is_empty());
+}
+
+#[test]
+fn test_product_metadata() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+    let chair = Metadata {
+        description: "Solid wood, slightly scratched",
+        condition: ProductCondition::Used as u8,
+        content_hash: crypto::hash(b"chair photo"),
+        attributes: vec![attribute("color", "brown"), attribute("material", "oak")],
+        ..Metadata::new("Oak chair", "CHAIR-1")
+    };
+    let lamp = Metadata {
+        description: "LED",
+        attributes: vec![attribute("color", "white")],
+        ..Metadata::new("Desk lamp", "LAMP-1")
+    };
+    execute(&mut testkit, make_product_tx(&alice, &chair, 0));
+    execute(&mut testkit, make_product_tx(&alice, &lamp, 0));
+
+    // Product id commits to all metadata.
+    let chair_id = chair.product().hash();
+    let snapshot = testkit.snapshot();
+    let product = AuctionSchema::new(&snapshot).products_states().get(&chair_id).unwrap().product();
+    assert_eq!(product.content_hash(), &crypto::hash(b"chair photo"));
+    assert_eq!(product.attribute("material"), Some("oak".to_string()));
+    assert_eq!(product.attribute("size"), None);
+
+    let text = ProductSearchQuery { text: Some("WOOD".to_string()), ..search_query() };
+    assert_eq!(search(&testkit, &text), vec!["Oak chair"]);
+    let condition = ProductSearchQuery { condition: Some(1), ..search_query() };
+    assert_eq!(search(&testkit, &condition), vec!["Oak chair"]);
+    let key = ProductSearchQuery { key: Some("color".to_string()), ..search_query() };
+    assert_eq!(search(&testkit, &key), vec!["Desk lamp", "Oak chair"]);
+    let value = ProductSearchQuery { value: Some("white".to_string()), ..key.clone() };
+    assert_eq!(search(&testkit, &value), vec!["Desk lamp"]);
+    let missing = ProductSearchQuery { key: Some("size".to_string()), ..search_query() };
+    assert!(search(&testkit, &missing).is_empty());
+}
+
+#[test]
+fn test_invalid_product_metadata() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+
+    let duplicate_keys = Metadata {
+        attributes: vec![attribute("color", "brown"), attribute("color", "white")],
+        ..Metadata::new("Chair", "CHAIR-1")
+    };
+    let tx = make_product_tx(&alice, &duplicate_keys, 0);
+    assert_error(&mut testkit, tx, Error::InvalidProductParameters);
+    let unknown_condition = Metadata { condition: 4, ..Metadata::new("Chair", "CHAIR-1") };
+    let tx = make_product_tx(&alice, &unknown_condition, 0);
+    assert_error(&mut testkit, tx, Error::InvalidProductParameters);
+    let tx = make_product_tx(&alice, &Metadata::new("Chair", "CHAIR-1"), 51);
+    assert_error(&mut testkit, tx, Error::InvalidProductParameters);
+    let unknown_category = Metadata { category: "Furniture", ..Metadata::new("Chair", "CHAIR-1") };
+    let tx = make_product_tx(&alice, &unknown_category, 0);
+    assert_error(&mut testkit, tx, Error::CategoryNotFound);
+}
+
+#[test]
+fn test_barcodes() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let chair = make_product(&mut testkit, &alice, "Chair", "4006381333931", 0);
+
+    // Barcodes are unique among all products.
+    let tx = make_product_tx(&bob, &Metadata::new("Table", "4006381333931"), 0);
+    assert_error(&mut testkit, tx, Error::BarcodeAlreadyUsed);
+    let tx = make_product_tx(&bob, &Metadata::new("Table", "AUTO-5"), 0);
+    assert_error(&mut testkit, tx, Error::InvalidProductParameters);
+
+    // Barcodes of the products without one are generated in sequence.
+    execute(&mut testkit, make_product_tx(&bob, &Metadata::new("Stool", ""), 0));
+    execute(&mut testkit, make_product_tx(&bob, &Metadata::new("Stool", ""), 0));
+    let snapshot = testkit.snapshot();
+    let schema = AuctionSchema::new(&snapshot);
+    let first = Metadata::new("Stool", "AUTO-0").product().hash();
+    let second = Metadata::new("Stool", "AUTO-1").product().hash();
+    assert_eq!(schema.barcodes().get(&"AUTO-0".to_string()), Some(first));
+    assert_eq!(schema.barcodes().get(&"AUTO-1".to_string()), Some(second));
+    assert_eq!(owner(&testkit, &second), bob.public_key);
+
+    let product_state: Option<ProductState> = testkit
+        .api()
+        .public(ApiKind::Service("auction"))
+        .query(&BarcodeQuery { barcode: "4006381333931".to_string() })
+        .get("v1/product/barcode")
+        .unwrap();
+    assert_eq!(product_state.unwrap().product().hash(), chair);
+}
+
+#[test]
+fn test_category_taxonomy() {
+    let mut testkit = create_testkit();
+    let tx = add_category_tx(&testkit, "Furniture", "", 0);
+    execute(&mut testkit, tx);
+    let tx = add_category_tx(&testkit, "Chairs", "Furniture", 0);
+    execute(&mut testkit, tx);
+
+    let tx = add_category_tx(&testkit, "Chairs", "", 1);
+    assert_error(&mut testkit, tx, Error::CategoryAlreadyExists);
+    let tx = add_category_tx(&testkit, "Lamps", "Lighting", 0);
+    assert_error(&mut testkit, tx, Error::CategoryNotFound);
+    let tx = add_category_tx(&testkit, "", "", 0);
+    assert_error(&mut testkit, tx, Error::InvalidCategoryName);
+
+    // Only administrators maintain the taxonomy.
+    let (public_key, secret_key) = crypto::gen_keypair();
+    let tx = AddCategory::new(&public_key, "Lamps", "", "", 0, &secret_key);
+    assert_error(&mut testkit, tx, Error::UnauthorizedTransaction);
+    let tx = RemoveCategory::new(&public_key, "Chairs", 0, &secret_key);
+    assert_error(&mut testkit, tx, Error::UnauthorizedTransaction);
+
+    let categories: Vec<Category> = testkit
+        .api()
+        .public(ApiKind::Service("auction"))
+        .get("v1/categories")
+        .unwrap();
+    let mut names: Vec<_> = categories.iter().map(|category| category.name()).collect();
+    names.sort();
+    assert_eq!(names, vec!["Chairs", "Furniture"]);
+
+    // Category with subcategories can't be removed.
+    let tx = remove_category_tx(&testkit, "Furniture", 0);
+    assert_error(&mut testkit, tx, Error::CategoryInUse);
+    let tx = remove_category_tx(&testkit, "Chairs", 0);
+    execute(&mut testkit, tx);
+    let tx = remove_category_tx(&testkit, "Chairs", 1);
+    assert_error(&mut testkit, tx, Error::CategoryNotFound);
+    let tx = remove_category_tx(&testkit, "Furniture", 1);
+    execute(&mut testkit, tx);
+}
+
+#[test]
+fn test_category_browsing() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+    let tx = add_category_tx(&testkit, "Chairs", "", 0);
+    execute(&mut testkit, tx);
+    let chair = Metadata { category: "Chairs", ..Metadata::new("Chair", "CHAIR-1") };
+    execute(&mut testkit, make_product_tx(&alice, &chair, 0));
+    let chair_id = chair.product().hash();
+    make_product(&mut testkit, &alice, "Lamp", "LAMP-1", 0);
+
+    let query = CategoryQuery { name: "Chairs".to_string() };
+    let products: Vec<ProductState> = testkit
+        .api()
+        .public(ApiKind::Service("auction"))
+        .query(&query)
+        .get("v1/category/products")
+        .unwrap();
+    assert_eq!(products.len(), 1);
+    assert_eq!(products[0].product().hash(), chair_id);
+
+    // Open auctions are listed in the categories of their products.
+    let params = AuctionParams::english(vec![chair_id], 10);
+    let auction_id = create_auction(&mut testkit, &alice, params);
+    let auctions: Vec<AuctionState> = testkit
+        .api()
+        .public(ApiKind::Service("auction"))
+        .query(&query)
+        .get("v1/category/auctions")
+        .unwrap();
+    assert_eq!(auctions.len(), 1);
+    assert_eq!(auctions[0].id(), auction_id);
+
+    wait_for_close(&mut testkit, auction_id);
+    let snapshot = testkit.snapshot();
+    let schema = AuctionSchema::new(&snapshot);
+    assert!(!schema.category_auctions(&category_id("Chairs")).contains(&auction_id));
+
+    // Category with products can't be removed, retired products leave the category.
+    let tx = remove_category_tx(&testkit, "Chairs", 0);
+    assert_error(&mut testkit, tx, Error::CategoryInUse);
+    execute(&mut testkit, burn_tx(&alice, &chair_id));
+    let tx = remove_category_tx(&testkit, "Chairs", 1);
+    execute(&mut testkit, tx);
+}
+
+#[test]
+fn test_share_trading() {
+    let mut testkit = create_testkit();
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let yacht = make_product(&mut testkit, &alice, "Yacht", "YACHT-1", 0);
+
+    assert_error(&mut testkit, split_tx(&alice, &yacht, 1), Error::InvalidShares);
+    execute(&mut testkit, split_tx(&alice, &yacht, 10));
+    assert_eq!(shares(&testkit, &alice, &yacht), 10);
+    assert_error(&mut testkit, split_tx(&alice, &yacht, 5), Error::ProductSplit);
+
+    // Split product is traded by shares only.
+    let tx = ListForSale::new(&alice.public_key, &yacht, 50, &alice.secret_key);
+    assert_error(&mut testkit, tx, Error::ProductSplit);
+    let tx = transfer_product_tx(&alice, &bob, &yacht);
+    assert_error(&mut testkit, tx, Error::ProductSplit);
+
+    execute(&mut testkit, ListShares::new(&alice.public_key, &yacht, 4, 5, &alice.secret_key));
+    let tx = BuyShares::new(&bob.public_key, &yacht, &alice.public_key, 3, 4, 0, &bob.secret_key);
+    assert_error(&mut testkit, tx, Error::ListingPriceMismatch);
+    let tx = BuyShares::new(&bob.public_key, &yacht, &alice.public_key, 3, 5, 0, &bob.secret_key);
+    execute(&mut testkit, tx);
+    assert_eq!(shares(&testkit, &alice, &yacht), 7);
+    assert_eq!(shares(&testkit, &bob, &yacht), 3);
+    assert_eq!(funds(&testkit, &alice), (115, 0));
+    assert_eq!(funds(&testkit, &bob), (85, 0));
+
+    // The rest of the shares stays offered.
+    let snapshot = testkit.snapshot();
+    let listing = AuctionSchema::new(&snapshot).share_listings(&yacht).get(&alice.public_key);
+    assert_eq!(listing.unwrap().amount(), 1);
+    let tx = BuyShares::new(&bob.public_key, &yacht, &alice.public_key, 2, 5, 1, &bob.secret_key);
+    assert_error(&mut testkit, tx, Error::InsufficientShares);
+
+    // Minority holder neither auctions nor merges the product.
+    let params = AuctionParams::english(vec![yacht], 10);
+    assert_error(&mut testkit, create_auction_tx(&bob, params), Error::ProductNotOwned);
+    let tx = MergeShares::new(&bob.public_key, &yacht, &bob.secret_key);
+    assert_error(&mut testkit, tx, Error::InsufficientShares);
+
+    // Holder of all shares merges them back into the product.
+    execute(&mut testkit, transfer_shares_tx(&alice, &bob, &yacht, 7, 0));
+    execute(&mut testkit, MergeShares::new(&bob.public_key, &yacht, &bob.secret_key));
+    assert_eq!(owner(&testkit, &yacht), bob.public_key);
+    let snapshot = testkit.snapshot();
+    assert_eq!(AuctionSchema::new(&snapshot).products_states().get(&yacht).unwrap().shares(), 0);
+    let changes = vec![
+        OwnershipChange::Creation,
+        OwnershipChange::ShareSale,
+        OwnershipChange::ShareTransfer,
+        OwnershipChange::Transfer,
+    ];
+    assert_eq!(history(&testkit, &yacht), changes);
+}
+
+#[test]
+fn test_split_product_auction_proceeds() {
+    let mut testkit = create_testkit();
+    let owner_user = create_user(&mut testkit, "Owner");
+    let holder = create_user(&mut testkit, "Holder");
+    let winner = create_user(&mut testkit, "Winner");
+    let yacht = make_product(&mut testkit, &owner_user, "Yacht", "YACHT-1", 0);
+
+    execute(&mut testkit, split_tx(&owner_user, &yacht, 10));
+    execute(&mut testkit, transfer_shares_tx(&owner_user, &holder, &yacht, 4, 0));
+
+    // The majority holder auctions the product, shares are frozen until it is over.
+    let params = AuctionParams::english(vec![yacht], 10);
+    let auction_id = create_auction(&mut testkit, &owner_user, params);
+    let tx = transfer_shares_tx(&owner_user, &holder, &yacht, 6, 1);
+    assert_error(&mut testkit, tx, Error::ProductAlreadyAuctioned);
+
+    bid(&mut testkit, &winner, auction_id, 57);
+    wait_for_close(&mut testkit, auction_id);
+
+    // Proceeds are paid pro-rata, the rounding remainder goes to the seller.
+    assert_eq!(owner(&testkit, &yacht), winner.public_key);
+    assert_eq!(funds(&testkit, &owner_user), (135, 0));
+    assert_eq!(funds(&testkit, &holder), (122, 0));
+    assert_eq!(funds(&testkit, &winner), (43, 0));
+
+    let snapshot = testkit.snapshot();
+    let schema = AuctionSchema::new(&snapshot);
+    assert_eq!(schema.products_states().get(&yacht).unwrap().shares(), 0);
+    assert_eq!(schema.product_shares(&yacht).iter().count(), 0);
+    let changes = vec![
+        OwnershipChange::Creation,
+        OwnershipChange::ShareTransfer,
+        OwnershipChange::AuctionSale,
+    ];
+    assert_eq!(history(&testkit, &yacht), changes);
+}
+
+#[test]
+fn test_creator_royalty() {
+    let mut testkit = create_testkit();
+    let creator = create_user(&mut testkit, "Creator");
+    let alice = create_user(&mut testkit, "Alice");
+    let bob = create_user(&mut testkit, "Bob");
+    let painting = make_product(&mut testkit, &creator, "Painting", "PAINTING-1", 10);
+
+    // The creator receives the royalty of the first sale too.
+    let params = AuctionParams::english(vec![painting], 10);
+    let auction_id = create_auction(&mut testkit, &creator, params);
+    bid(&mut testkit, &alice, auction_id, 50);
+    wait_for_close(&mut testkit, auction_id);
+    assert_eq!(funds(&testkit, &creator), (150, 0));
+    assert_eq!(funds(&testkit, &alice), (50, 0));
+
+    // Resale pays the royalty to the creator and the rest to the seller.
+    let params = AuctionParams::english(vec![painting], 10);
+    let auction_id = create_auction(&mut testkit, &alice, params);
+    bid(&mut testkit, &bob, auction_id, 40);
+    wait_for_close(&mut testkit, auction_id);
+    assert_eq!(owner(&testkit, &painting), bob.public_key);
+    assert_eq!(funds(&testkit, &creator), (154, 0));
+    assert_eq!(funds(&testkit, &alice), (86, 0));
+    assert_eq!(funds(&testkit, &bob), (60, 0));
+
+    // Sales outside of auctions pay no royalty.
+    execute(&mut testkit, ListForSale::new(&bob.public_key, &painting, 30, &bob.secret_key));
+    let tx = Buy::new(&alice.public_key, &painting, 30, &alice.secret_key);
+    execute(&mut testkit, tx);
+    assert_eq!(funds(&testkit, &creator), (154, 0));
+    assert_eq!(funds(&testkit, &bob), (90, 0));
+}
+
+#[test]
+fn test_bundle_royalties() {
+    let mut testkit = create_testkit();
+    let painter = create_user(&mut testkit, "Painter");
+    let sculptor = create_user(&mut testkit, "Sculptor");
+    let seller = create_user(&mut testkit, "Seller");
+    let winner = create_user(&mut testkit, "Winner");
+    let painting = make_product(&mut testkit, &painter, "Painting", "PAINTING-1", 10);
+    let statue = make_product(&mut testkit, &sculptor, "Statue", "STATUE-1", 20);
+    execute(&mut testkit, transfer_product_tx(&painter, &seller, &painting));
+    execute(&mut testkit, transfer_product_tx(&sculptor, &seller, &statue));
+
+    // The price of the bundle is divided among its products equally.
+    let params = AuctionParams::english(vec![painting, statue], 10);
+    let auction_id = create_auction(&mut testkit, &seller, params);
+    bid(&mut testkit, &winner, auction_id, 60);
+    wait_for_close(&mut testkit, auction_id);
+    assert_eq!(funds(&testkit, &painter), (103, 0));
+    assert_eq!(funds(&testkit, &sculptor), (106, 0));
+    assert_eq!(funds(&testkit, &seller), (151, 0));
+    assert_eq!(funds(&testkit, &winner), (40, 0));
+}